@@ -70,8 +70,8 @@ async fn main() {
     assert_eq!(
         join(subscriber1.join(), subscriber2.join()).await,
         (
-            Some(Subscribing1(vec![42, 23])),
-            Some(Subscribing2(vec![42, 23]))
+            (Some(Subscribing1(vec![42, 23])), StopReason::Stopped),
+            (Some(Subscribing2(vec![42, 23])), StopReason::Stopped)
         )
     );
     // assert_eq!(subscriber1.consume().await, Ok(Subscribing1(vec![42, 23])));
@@ -11,8 +11,8 @@ impl Actor for Child {
         Ok(())
     }
 
-    async fn stopped(&mut self, _: &mut Context<Self>) {
-        println!("{self:?} stopped");
+    async fn stopped(&mut self, _: &mut Context<Self>, reason: StopReason) {
+        println!("{self:?} stopped: {reason}");
     }
 }
 
@@ -33,7 +33,7 @@ impl Actor for Root {
         ctx.create_child(|| Child(3));
         Ok(())
     }
-    async fn stopped(&mut self, _: &mut Context<Self>) {
+    async fn stopped(&mut self, _: &mut Context<Self>, _: StopReason) {
         println!("{self:?} stopped");
     }
 }
@@ -46,6 +46,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     root_addr.stop()?;
 
-    root_addr.await?;
+    println!("root stopped: {}", root_addr.await);
     Ok(())
 }
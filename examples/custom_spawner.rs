@@ -62,7 +62,7 @@ mod custom_spawner {
             Ok(())
         }
 
-        async fn stopped(&mut self, _ctx: &mut Context<Self>) {
+        async fn stopped(&mut self, _ctx: &mut Context<Self>, _: StopReason) {
             eprintln!("stopping actor")
         }
     }
@@ -16,7 +16,7 @@ impl Actor for MyActor {
         Ok(())
     }
 
-    async fn stopped(&mut self, _ctx: &mut Context<Self>) {
+    async fn stopped(&mut self, _ctx: &mut Context<Self>, _: StopReason) {
         println!("[Actor] stopped");
     }
 }
@@ -37,5 +37,5 @@ impl Handler<Stop> for MyActor {
 
 #[tokio::main]
 async fn main() {
-    MyActor(0).spawn().await.unwrap();
+    MyActor(0).spawn().await;
 }
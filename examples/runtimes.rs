@@ -18,7 +18,7 @@ impl Actor for MyActor {
         Ok(())
     }
 
-    async fn stopped(&mut self, _ctx: &mut Context<Self>) {
+    async fn stopped(&mut self, _ctx: &mut Context<Self>, _: StopReason) {
         println!("[Actor {}] stopped", self.0);
     }
 }
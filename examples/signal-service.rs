@@ -41,5 +41,5 @@ async fn main() {
 
     println!("kill me with Ctrl-C three times to stop the actor");
 
-    addr.await.unwrap();
+    addr.await;
 }
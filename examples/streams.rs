@@ -30,5 +30,5 @@ async fn main() {
         .on_stream(num_stream)
         .spawn();
 
-    addr.await.unwrap();
+    addr.await;
 }
@@ -19,7 +19,7 @@ impl Actor for SleepyActor {
         Ok(())
     }
 
-    async fn stopped(&mut self, _ctx: &mut Context<Self>) {
+    async fn stopped(&mut self, _ctx: &mut Context<Self>, _: StopReason) {
         println!("[ SleepyActor {} ] stopped", self.0);
     }
 }
@@ -68,7 +68,7 @@ async fn main() {
     assert!(addr.call(Sleep(Duration::from_secs(0))).await.is_ok());
     eprintln!("SleepyActor 2 is still alive, stopping");
     assert!(addr.to_addr().stop().is_ok());
-    assert!(addr.join().await.is_some());
+    assert!(matches!(addr.join().await, (Some(_), StopReason::Stopped)));
 
     // timeout and fail
     println!("SleepyActor 2 will be canceled after 1 second");
@@ -91,6 +91,6 @@ async fn main() {
             .await
             .is_err()
     );
-    assert!(addr.join().await.is_none());
+    assert!(matches!(addr.join().await, (None, StopReason::Timeout)));
     assert!(addr.to_addr().stop().is_err());
 }
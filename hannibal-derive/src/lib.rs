@@ -7,11 +7,23 @@ use syn::{DeriveInput, parse_macro_input};
 #[proc_macro_attribute]
 pub fn message(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut response: Option<syn::Type> = None;
+    let mut priority: Option<syn::Ident> = None;
+    let mut conflate = false;
+    let mut deadline_ms: Option<syn::LitInt> = None;
 
     let response_parser = syn::meta::parser(|meta| {
         if meta.path.is_ident("response") {
             response = Some(meta.value()?.parse()?);
             Ok(())
+        } else if meta.path.is_ident("priority") {
+            priority = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("deadline_ms") {
+            deadline_ms = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("conflate") {
+            conflate = true;
+            Ok(())
         } else {
             Ok(())
         }
@@ -23,6 +35,24 @@ pub fn message(args: TokenStream, input: TokenStream) -> TokenStream {
     } else {
         quote! { () }
     };
+    let priority = priority.map(|priority| {
+        quote! { const PRIORITY: ::hannibal::Priority = ::hannibal::Priority::#priority; }
+    });
+
+    let deadline = deadline_ms.map(|millis| {
+        quote! {
+            const DEADLINE: ::core::option::Option<::core::time::Duration> =
+                ::core::option::Option::Some(::core::time::Duration::from_millis(#millis));
+        }
+    });
+
+    let conflation_key = conflate.then(|| {
+        quote! {
+            fn conflation_key(&self) -> ::core::option::Option<::hannibal::ConflationKey> {
+                ::core::option::Option::Some(::hannibal::ConflationKey::of(self))
+            }
+        }
+    });
 
     let ast = syn::parse::<DeriveInput>(input).unwrap();
     let ident = &ast.ident;
@@ -30,6 +60,9 @@ pub fn message(args: TokenStream, input: TokenStream) -> TokenStream {
         #ast
         impl ::hannibal::Message for #ident {
             type Response = #response_type;
+            #priority
+            #deadline
+            #conflation_key
         }
     };
     generated.into()
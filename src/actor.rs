@@ -6,14 +6,18 @@ use crate::context::Context;
 mod build;
 
 #[cfg(any(feature = "tokio", feature = "async-std"))]
-mod builder;
+pub(crate) mod builder;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub mod fsm;
 pub mod service;
 pub mod spawner;
 
 pub(crate) mod restart_strategy;
+mod stop_reason;
 #[cfg(any(feature = "tokio", feature = "async-std"))]
 pub use build::build;
-pub use restart_strategy::RestartableActor;
+pub use restart_strategy::{Backoff, RestartIntensity, RestartableActor};
+pub use stop_reason::StopReason;
 
 /// Convenience type alias for `Box<dyn std::error::Error + Send + Sync>`.
 pub type DynResult<T = ()> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
@@ -35,8 +39,16 @@ pub trait Actor: Sized + Send + 'static {
         async { Ok(()) }
     }
 
+    /// Called when the actor is stopped.
+    ///
+    /// The `reason` tells you whether the actor was stopped deliberately or because something went wrong.
+    /// It is also called with [`StopReason::Restarting`] before the actor is restarted.
     #[allow(unused)]
-    fn stopped(&mut self, ctx: &mut Context<Self>) -> impl Future<Output = ()> + Send {
+    fn stopped(
+        &mut self,
+        ctx: &mut Context<Self>,
+        reason: StopReason,
+    ) -> impl Future<Output = ()> + Send {
         async {}
     }
 }
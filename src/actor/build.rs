@@ -29,6 +29,77 @@ use super::{Actor, builder, spawner};
 ///     .unbounded()
 ///     .spawn();
 /// ```
+///
+/// ### Example: Overflow policy
+/// By default senders wait while a bounded mailbox is full.
+/// Pick an [`OverflowPolicy`](`crate::OverflowPolicy`) to reject or drop messages instead.
+///
+/// ```no_run
+/// # #[derive(hannibal_derive::Actor,hannibal_derive::RestartableActor, Default)]
+/// # struct MyActor;
+/// // keep only the 6 most recent messages
+/// let addr = hannibal::build(MyActor)
+///     .bounded(6)
+///     .overflow(hannibal::OverflowPolicy::DropOldest)
+///     .spawn();
+/// ```
+///
+/// ### Example: Conflating mailbox
+/// A conflating mailbox only keeps the latest unprocessed message per key,
+/// see [`Conflate`](`crate::Conflate`) for how messages declare their key.
+///
+/// ```no_run
+/// # #[derive(hannibal_derive::Actor, Default)]
+/// # struct MyActor;
+/// # impl hannibal::Handler<PriceTick> for MyActor {
+/// #     async fn handle(&mut self, _: &mut hannibal::Context<Self>, _: PriceTick) {}
+/// # }
+/// #[hannibal::message(conflate)]
+/// struct PriceTick(&'static str, f64);
+///
+/// impl hannibal::Conflate for PriceTick {
+///     type Key = &'static str;
+///     fn key(&self) -> Self::Key {
+///         self.0
+///     }
+/// }
+///
+/// let addr = hannibal::build(MyActor)
+///     .unbounded()
+///     .conflating()
+///     .spawn();
+/// # async move {
+/// addr.send(PriceTick("ACME", 1.0)).await.unwrap();
+/// // replaces the first tick if it hasn't been handled yet
+/// addr.send(PriceTick("ACME", 1.1)).await.unwrap();
+/// # };
+/// ```
+///
+/// ### Example: Priority mailbox
+/// A priority mailbox always hands out messages with a higher [`Priority`](`crate::Priority`) first.
+/// Messages declare their priority via [`Message::PRIORITY`](`crate::Message::PRIORITY`),
+/// or the sender picks one with [`Addr::send_with_priority`](`crate::Addr::send_with_priority`).
+///
+/// ```no_run
+/// # #[derive(hannibal_derive::Actor, Default)]
+/// # struct MyActor;
+/// # impl hannibal::Handler<Flush> for MyActor {
+/// #     async fn handle(&mut self, _: &mut hannibal::Context<Self>, _: Flush) {}
+/// # }
+/// struct Flush;
+/// impl hannibal::Message for Flush {
+///     type Response = ();
+///     const PRIORITY: hannibal::Priority = hannibal::Priority::High;
+/// }
+///
+/// let addr = hannibal::build(MyActor)
+///     .unbounded_priority()
+///     .spawn();
+/// # async move {
+/// // overtakes all queued messages with a lower priority
+/// addr.send(Flush).await.unwrap();
+/// # };
+/// ```
 /// ## 2. Should the actor create a fresh object on restart?
 /// If you restart the actor its [`started()`](`Actor::started`) method will be called.
 /// You don't need to clean up and reset the actor's state if you configure it to be recreated from `Default` at spawn-time.
@@ -92,6 +163,31 @@ use super::{Actor, builder, spawner};
 ///         .bounded_on_stream(10, the_stream)
 ///         .spawn();
 /// ```
+///
+/// ### Example: Restartable stream handler
+/// An actor that owns a single stream can't be restarted, the stream would be gone.
+/// Pass a stream factory instead and the actor subscribes to a fresh stream on every restart.
+///
+/// ```no_run
+/// # use hannibal::{Context, StreamHandler};
+/// # use hannibal_derive::{Actor, RestartableActor};
+/// #[derive(Actor, RestartableActor, Default)]
+/// struct Connector;
+///
+/// impl StreamHandler<i32> for Connector {
+///     async fn handle(&mut self, _ctx: &mut Context<Self>, msg: i32) {
+///         println!("[Connection] Received: {}", msg);
+///     }
+/// }
+///
+/// let mut addr = hannibal::build(Connector)
+///         .unbounded()
+///         .recreate_from_default()
+///         .with_stream_factory(|| futures::stream::iter(0..19))
+///         .spawn();
+///
+/// addr.restart().unwrap();
+/// ```
 /// ## 4. Timeouts
 ///
 /// One problem that you have to address when handling messages is that you can't wait forever.
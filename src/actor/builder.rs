@@ -1,17 +1,22 @@
 #![warn(missing_docs)]
 use std::{marker::PhantomData, time::Duration};
 
+use futures::future::BoxFuture;
+
 use crate::{
     Addr, StreamHandler,
     actor::service::Service,
     addr::OwningAddr,
-    channel::Channel,
+    channel::{ChannelConfig, OverflowPolicy},
     environment::{self, EnvironmentConfig},
 };
 
 use super::{
-    Actor, RestartableActor,
-    restart_strategy::{NonRestartable, RecreateFromDefault, RestartOnly, RestartStrategy},
+    Actor, DynResult, RestartableActor,
+    restart_strategy::{
+        Backoff, NonRestartable, RecreateFromDefault, RestartIntensity, RestartOnly,
+        RestartStrategy,
+    },
     spawner::Spawner,
 };
 
@@ -32,7 +37,7 @@ where
     P: Spawner<A>,
 {
     base: BaseActorBuilder<A, P>,
-    channel: Channel<A>,
+    channel: ChannelConfig,
     restart: PhantomData<R>,
 }
 
@@ -47,21 +52,41 @@ where
     stream: S,
 }
 
+pub struct StreamFactoryActorBuilder<A, P, R, F, S>
+where
+    F: FnMut() -> S + Send + 'static,
+    S: futures::Stream + Unpin + Send + 'static,
+    S::Item: 'static + Send,
+    A: StreamHandler<S::Item>,
+    P: Spawner<A>,
+    R: RestartStrategy<A>,
+{
+    with_channel: ActorBuilderWithChannel<A, P, R>,
+    make_stream: F,
+    stream: PhantomData<fn() -> S>,
+}
+
 /// add channel
 impl<A, P> BaseActorBuilder<A, P>
 where
     A: Actor,
     P: Spawner<A>,
 {
-    pub(crate) fn new(actor: A) -> Self {
+    pub(crate) fn new(actor: A) -> Self
+    where
+        P: 'static,
+    {
         Self {
             actor,
             spawner: PhantomData,
-            config: Default::default(),
+            config: EnvironmentConfig::with_spawner::<A, P>(),
         }
     }
 
-    const fn with_channel(self, channel: Channel<A>) -> ActorBuilderWithChannel<A, P, RestartOnly> {
+    const fn with_channel(
+        self,
+        channel: ChannelConfig,
+    ) -> ActorBuilderWithChannel<A, P, RestartOnly> {
         ActorBuilderWithChannel {
             base: self,
             restart: PhantomData,
@@ -78,12 +103,42 @@ where
         self
     }
 
-    pub fn bounded(self, capacity: usize) -> ActorBuilderWithChannel<A, P, RestartOnly> {
-        self.with_channel(Channel::bounded(capacity))
+    /// Use a mailbox that holds up to `capacity` messages.
+    ///
+    /// The capacity is strict and shared by all senders, they don't get an extra slot each.
+    /// Once `capacity` messages are queued the next send waits,
+    /// see [`overflow`](`ActorBuilderWithChannel::overflow`) for alternatives.
+    pub const fn bounded(self, capacity: usize) -> ActorBuilderWithChannel<A, P, RestartOnly> {
+        self.with_channel(ChannelConfig {
+            capacity: Some(capacity),
+            ..ChannelConfig::DEFAULT
+        })
     }
 
-    pub fn unbounded(self) -> ActorBuilderWithChannel<A, P, RestartOnly> {
-        self.with_channel(Channel::unbounded())
+    pub const fn unbounded(self) -> ActorBuilderWithChannel<A, P, RestartOnly> {
+        self.with_channel(ChannelConfig::DEFAULT)
+    }
+
+    /// Use a priority mailbox that holds up to `capacity` messages of any [`Priority`](`crate::Priority`).
+    ///
+    /// Stopping and restarting the actor goes ahead of all queued messages.
+    pub const fn bounded_priority(
+        self,
+        capacity: usize,
+    ) -> ActorBuilderWithChannel<A, P, RestartOnly> {
+        self.with_channel(ChannelConfig {
+            capacity: Some(capacity),
+            priority: true,
+            ..ChannelConfig::DEFAULT
+        })
+    }
+
+    /// Use an unbounded priority mailbox.
+    pub const fn unbounded_priority(self) -> ActorBuilderWithChannel<A, P, RestartOnly> {
+        self.with_channel(ChannelConfig {
+            priority: true,
+            ..ChannelConfig::DEFAULT
+        })
     }
 
     /// Create a non-restarable on that stream
@@ -93,9 +148,12 @@ where
         S::Item: 'static + Send,
         A: StreamHandler<S::Item>,
     {
-        self.with_channel(Channel::bounded(capacity))
-            .non_restartable()
-            .with_stream(stream)
+        self.with_channel(ChannelConfig {
+            capacity: Some(capacity),
+            ..ChannelConfig::DEFAULT
+        })
+        .non_restartable()
+        .with_stream(stream)
     }
 
     /// Create a non-restarable on that stream
@@ -105,7 +163,7 @@ where
         S::Item: 'static + Send,
         A: StreamHandler<S::Item>,
     {
-        self.with_channel(Channel::unbounded())
+        self.with_channel(ChannelConfig::DEFAULT)
             .non_restartable()
             .with_stream(stream)
     }
@@ -130,6 +188,36 @@ where
     }
 }
 
+/// add stream factory
+impl<A, P, R> ActorBuilderWithChannel<A, P, R>
+where
+    A: RestartableActor,
+    P: Spawner<A>,
+    R: RestartStrategy<A> + 'static,
+{
+    /// Attach the actor to streams created by `make_stream`.
+    ///
+    /// Unlike [`with_stream`](`Self::with_stream`) this keeps the actor restartable.
+    /// `make_stream` is called once on spawn and again on every restart,
+    /// so the actor subscribes to a fresh stream each time.
+    pub fn with_stream_factory<F, S>(
+        self,
+        make_stream: F,
+    ) -> StreamFactoryActorBuilder<A, P, R, F, S>
+    where
+        F: FnMut() -> S + Send + 'static,
+        S: futures::Stream + Unpin + Send + 'static,
+        S::Item: 'static + Send,
+        A: StreamHandler<S::Item>,
+    {
+        StreamFactoryActorBuilder {
+            with_channel: self,
+            make_stream,
+            stream: PhantomData,
+        }
+    }
+}
+
 /// make non restartable
 impl<A, P, R> ActorBuilderWithChannel<A, P, R>
 where
@@ -139,7 +227,8 @@ where
 {
     /// Build a non-restartable Actor.
     ///
-    /// Only non-restartable actors can handle streams.
+    /// Only non-restartable actors can handle a single stream,
+    /// see [`with_stream_factory`](`Self::with_stream_factory`) for restartable stream handlers.
     pub fn non_restartable(self) -> ActorBuilderWithChannel<A, P, NonRestartable> {
         ActorBuilderWithChannel {
             base: self.base,
@@ -148,6 +237,28 @@ where
         }
     }
 
+    /// Decide what a bounded mailbox does with messages that arrive while it is full.
+    ///
+    /// Defaults to [`OverflowPolicy::Block`], unbounded mailboxes never overflow.
+    pub const fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.channel.overflow = policy;
+        self
+    }
+
+    /// Let a new message replace a queued one with the same key, see [`Conflate`](`crate::Conflate`).
+    ///
+    /// Messages that don't return a [`ConflationKey`](`crate::ConflationKey`) are queued as usual.
+    pub const fn conflating(mut self) -> Self {
+        self.channel.conflate = true;
+        self
+    }
+
+    /// Limit how many messages the actor can [stash](`crate::Context::stash`), 1000 by default.
+    pub const fn stash_capacity(mut self, capacity: usize) -> Self {
+        self.base.config.stash_capacity = Some(capacity);
+        self
+    }
+
     /// Set a maximum time that a handler can take to
     pub const fn timeout(mut self, timeout: Duration) -> Self {
         self.base.config.timeout = Some(timeout);
@@ -155,13 +266,60 @@ where
     }
 
     /// Terminate the actor if a timeout is exceeded.
-    /// TODO: add a `.restart_on_timeout()` method
+    ///
+    /// Restartable actors can be restarted instead, see [`restart_on_timeout`](`Self::restart_on_timeout`).
     pub const fn fail_on_timeout(mut self, fail: bool) -> Self {
         self.base.config.fail_on_timeout = fail;
         self
     }
 }
 
+/// restart on panic
+impl<A, P, R> ActorBuilderWithChannel<A, P, R>
+where
+    A: RestartableActor,
+    P: Spawner<A>,
+    R: RestartStrategy<A> + 'static,
+{
+    /// Restart the actor if a handler panics instead of stopping it.
+    ///
+    /// The actor keeps its [`Addr`], pending calls to the panicking handler are canceled.
+    pub const fn restart_on_panic(mut self, restart: bool) -> Self {
+        self.base.config.restart_on_panic = restart;
+        self
+    }
+
+    /// Give up if the actor is restarted more than `max_restarts` times within `within`.
+    ///
+    /// The actor then stops with [`ActorError::TooManyRestarts`](`crate::error::ActorError::TooManyRestarts`).
+    pub const fn restart_intensity(mut self, max_restarts: usize, within: Duration) -> Self {
+        self.base.config.restart_intensity = Some(RestartIntensity {
+            max_restarts,
+            within,
+        });
+        self
+    }
+
+    /// Wait before restarting the actor.
+    ///
+    /// The delay is awaited with the spawner's `sleep`, messages are queued in the meantime.
+    pub const fn backoff(mut self, backoff: Backoff) -> Self {
+        self.base.config.backoff = backoff;
+        self
+    }
+
+    /// Restart the actor if a handler exceeds its [`timeout`](`Self::timeout`) instead of ignoring it or stopping the actor.
+    ///
+    /// The actor is restarted according to its restart strategy,
+    /// e.g. [recreated from `Default`](`Self::recreate_from_default`), and keeps its [`Addr`].
+    /// Takes precedence over [`fail_on_timeout`](`Self::fail_on_timeout`).
+    /// Items of a [stream factory](`Self::with_stream_factory`) are held to the same timeout.
+    pub const fn restart_on_timeout(mut self, restart: bool) -> Self {
+        self.base.config.restart_on_timeout = restart;
+        self
+    }
+}
+
 /// make recreate from `Default` on restart
 impl<A, P, R> ActorBuilderWithChannel<A, P, R>
 where
@@ -192,7 +350,8 @@ where
             ..
         } = self;
 
-        let env = environment::Environment::<A, R>::from_channel(channel).with_config(config);
+        let env =
+            environment::Environment::<A, R>::from_channel(channel.build()).with_config(config);
         let (event_loop, addr) = env.create_loop(actor);
         let handle = P::spawn_actor(event_loop);
         OwningAddr { addr, handle }
@@ -205,13 +364,64 @@ where
             ..
         } = self;
 
-        let env = environment::Environment::<A, R>::from_channel(channel).with_config(config);
+        let env =
+            environment::Environment::<A, R>::from_channel(channel.build()).with_config(config);
         let (event_loop, addr) = env.create_loop(actor);
         let _handle = P::spawn_actor(event_loop);
         addr
     }
 }
 
+/// supervise actor
+impl<A, P, R> ActorBuilderWithChannel<A, P, R>
+where
+    A: Actor,
+    P: Spawner<A>,
+    R: RestartStrategy<A> + 'static,
+{
+    /// The actor's event loop and address, the caller is responsible for spawning the loop.
+    pub(crate) fn create_loop(self) -> (BoxFuture<'static, DynResult<A>>, Addr<A>) {
+        let ActorBuilderWithChannel {
+            base: BaseActorBuilder { actor, config, .. },
+            channel,
+            ..
+        } = self;
+
+        let env =
+            environment::Environment::<A, R>::from_channel(channel.build()).with_config(config);
+        let (event_loop, addr) = env.create_loop(actor);
+        (Box::pin(event_loop), addr)
+    }
+}
+
+/// spawn pool
+impl<A, P, R> ActorBuilderWithChannel<A, P, R>
+where
+    A: Actor + Clone,
+    P: Spawner<A> + 'static,
+    R: RestartStrategy<A> + 'static,
+{
+    /// Spawn `size` clones of the actor as a [`Pool`](`crate::Pool`).
+    ///
+    /// Every worker gets its own mailbox configured like this builder's,
+    /// workers that are added when the pool [grows](`crate::Pool::resize`) are cloned from the same actor.
+    pub fn spawn_pool(self, size: usize) -> crate::Pool<A> {
+        let ActorBuilderWithChannel {
+            base: BaseActorBuilder { actor, config, .. },
+            channel,
+            ..
+        } = self;
+
+        crate::Pool::new(size, move || {
+            let env = environment::Environment::<A, R>::from_channel(channel.build())
+                .with_config(config.clone());
+            let (event_loop, addr) = env.create_loop(actor.clone());
+            let _handle = P::spawn_actor(event_loop);
+            addr
+        })
+    }
+}
+
 /// register service
 impl<A, P, R> ActorBuilderWithChannel<A, P, R>
 where
@@ -244,7 +454,7 @@ where
             stream,
         } = self;
 
-        let env = environment::Environment::<A, NonRestartable>::from_channel(channel)
+        let env = environment::Environment::<A, NonRestartable>::from_channel(channel.build())
             .with_config(config);
         let (event_loop, addr) = env.create_loop_on_stream(actor, stream);
         let _handle = P::spawn_actor(event_loop);
@@ -262,10 +472,60 @@ where
             stream,
         } = self;
 
-        let env = environment::Environment::<A, NonRestartable>::from_channel(channel)
+        let env = environment::Environment::<A, NonRestartable>::from_channel(channel.build())
             .with_config(config);
         let (event_loop, addr) = env.create_loop_on_stream(actor, stream);
         let handle = P::spawn_actor(event_loop);
         OwningAddr { addr, handle }
     }
 }
+
+/// spawn actor on stream factory, restartable
+impl<A, P, R, F, S> StreamFactoryActorBuilder<A, P, R, F, S>
+where
+    F: FnMut() -> S + Send + 'static,
+    S: futures::Stream + Unpin + Send + 'static,
+    S::Item: 'static + Send,
+    A: StreamHandler<S::Item>,
+    A: Actor,
+    P: Spawner<A>,
+    R: RestartStrategy<A> + 'static,
+{
+    pub fn spawn(self) -> Addr<A> {
+        let Self {
+            with_channel:
+                ActorBuilderWithChannel {
+                    base: BaseActorBuilder { actor, config, .. },
+                    channel,
+                    ..
+                },
+            make_stream,
+            ..
+        } = self;
+
+        let env =
+            environment::Environment::<A, R>::from_channel(channel.build()).with_config(config);
+        let (event_loop, addr) = env.create_loop_on_stream_factory(actor, make_stream);
+        let _handle = P::spawn_actor(event_loop);
+        addr
+    }
+
+    pub fn spawn_owning(self) -> OwningAddr<A> {
+        let Self {
+            with_channel:
+                ActorBuilderWithChannel {
+                    base: BaseActorBuilder { actor, config, .. },
+                    channel,
+                    ..
+                },
+            make_stream,
+            ..
+        } = self;
+
+        let env =
+            environment::Environment::<A, R>::from_channel(channel.build()).with_config(config);
+        let (event_loop, addr) = env.create_loop_on_stream_factory(actor, make_stream);
+        let handle = P::spawn_actor(event_loop);
+        OwningAddr { addr, handle }
+    }
+}
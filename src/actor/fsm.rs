@@ -0,0 +1,429 @@
+//! Provides the `FsmActor` trait for actors that are finite state machines.
+//!
+//! The states are usually an enum, the actor keeps its current state in an [`Fsm`]
+//! and moves between states via [`FsmActor::transition`].
+//! Every state can have a timeout that moves the actor on if it stays in that state for too long.
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use hannibal::{prelude::*, fsm::{Fsm, FsmActor}};
+//! #[derive(Clone, Debug, PartialEq)]
+//! enum Link {
+//!     Connecting,
+//!     Online,
+//!     Offline,
+//! }
+//!
+//! struct Client {
+//!     fsm: Fsm<Link>,
+//! }
+//!
+//! impl Actor for Client {
+//!     async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+//!         self.enter_initial_state(ctx).await;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl FsmActor for Client {
+//!     type State = Link;
+//!
+//!     fn fsm(&mut self) -> &mut Fsm<Link> {
+//!         &mut self.fsm
+//!     }
+//!
+//!     // give up if the connection is not established within 5 seconds
+//!     fn state_timeout(&self, state: &Link) -> Option<(Duration, Link)> {
+//!         (*state == Link::Connecting).then_some((Duration::from_secs(5), Link::Offline))
+//!     }
+//! }
+//!
+//! #[derive(Debug, Message)]
+//! struct Connected;
+//!
+//! impl Handler<Connected> for Client {
+//!     async fn handle(&mut self, ctx: &mut Context<Self>, _: Connected) {
+//!         self.transition(ctx, Link::Online).await;
+//!     }
+//! }
+//!
+//! # async move {
+//! let addr = Client { fsm: Fsm::new(Link::Connecting) }.spawn();
+//! addr.send(Connected).await.unwrap();
+//! # };
+//! ```
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use futures::future::AbortHandle;
+
+use super::{Actor, spawner::SpawnSelf};
+use crate::{Context, environment::Payload};
+
+/// What caused a [`Transition`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// The actor called [`FsmActor::transition`].
+    Event,
+    /// The previous state timed out, see [`FsmActor::state_timeout`].
+    Timeout,
+}
+
+/// An entry in the transition log of an [`Fsm`].
+#[derive(Clone, Debug)]
+pub struct Transition<S> {
+    /// The state that was left.
+    pub from: S,
+    /// The state that was entered.
+    pub to: S,
+    /// What caused the transition.
+    pub trigger: Trigger,
+    /// When the transition happened.
+    pub at: Instant,
+}
+
+/// The state of an [`FsmActor`] and the log of its most recent transitions.
+pub struct Fsm<S> {
+    state: S,
+    entered: Instant,
+    generation: u64,
+    timer: Option<AbortHandle>,
+    log: VecDeque<Transition<S>>,
+    log_capacity: usize,
+}
+
+impl<S> Fsm<S> {
+    /// The number of transitions that are kept by default.
+    pub const DEFAULT_LOG_CAPACITY: usize = 64;
+
+    /// Start in the `initial` state.
+    pub fn new(initial: S) -> Self {
+        Self::with_log_capacity(initial, Self::DEFAULT_LOG_CAPACITY)
+    }
+
+    /// Start in the `initial` state and keep the last `capacity` transitions.
+    pub fn with_log_capacity(initial: S, capacity: usize) -> Self {
+        Self {
+            state: initial,
+            entered: Instant::now(),
+            generation: 0,
+            timer: None,
+            log: VecDeque::with_capacity(capacity.min(Self::DEFAULT_LOG_CAPACITY)),
+            log_capacity: capacity,
+        }
+    }
+
+    /// The current state.
+    pub const fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// How long the machine has been in the current state.
+    pub fn time_in_state(&self) -> Duration {
+        self.entered.elapsed()
+    }
+
+    /// The most recent transitions, oldest first.
+    pub fn transitions(&self) -> impl Iterator<Item = &Transition<S>> {
+        self.log.iter()
+    }
+
+    fn record(&mut self, transition: Transition<S>) {
+        if self.log_capacity == 0 {
+            return;
+        }
+        if self.log.len() >= self.log_capacity {
+            self.log.pop_front();
+        }
+        self.log.push_back(transition);
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for Fsm<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fsm")
+            .field("state", &self.state)
+            .field("transitions", &self.log.len())
+            .finish()
+    }
+}
+
+/// An actor that is a finite state machine, see the [module documentation](`self`).
+pub trait FsmActor: Actor {
+    /// The states of the machine, usually an enum.
+    type State: Clone + fmt::Debug + Send + Sync + 'static;
+
+    /// Where the actor keeps its [`Fsm`].
+    fn fsm(&mut self) -> &mut Fsm<Self::State>;
+
+    /// Called when the actor enters `state`, including the initial state.
+    #[allow(unused)]
+    fn on_enter(
+        &mut self,
+        ctx: &mut Context<Self>,
+        state: &Self::State,
+    ) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called when the actor leaves `state`.
+    #[allow(unused)]
+    fn on_exit(
+        &mut self,
+        ctx: &mut Context<Self>,
+        state: &Self::State,
+    ) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Transition to another state if the actor stays in `state` for longer than the returned duration.
+    ///
+    /// The timeout is waited for with the spawner's `sleep` and is canceled by any other transition.
+    #[allow(unused)]
+    fn state_timeout(&self, state: &Self::State) -> Option<(Duration, Self::State)> {
+        None
+    }
+
+    /// Enter the initial state, call this from [`Actor::started`].
+    ///
+    /// Calls [`on_enter`](`FsmActor::on_enter`) and starts the initial state's timeout.
+    fn enter_initial_state(&mut self, ctx: &mut Context<Self>) -> impl Future<Output = ()> + Send {
+        async move {
+            let state = self.fsm().state.clone();
+            self.fsm().entered = Instant::now();
+            self.on_enter(ctx, &state).await;
+            start_timer(self, ctx);
+        }
+    }
+
+    /// Leave the current state and enter `next`.
+    ///
+    /// Transitions to the current state leave and re-enter it, which also restarts its timeout.
+    fn transition(
+        &mut self,
+        ctx: &mut Context<Self>,
+        next: Self::State,
+    ) -> impl Future<Output = ()> + Send {
+        change_state(self, ctx, next, Trigger::Event)
+    }
+}
+
+async fn change_state<A: FsmActor>(
+    actor: &mut A,
+    ctx: &mut Context<A>,
+    next: A::State,
+    trigger: Trigger,
+) {
+    let previous = actor.fsm().state.clone();
+    log::trace!("{} {previous:?} -> {next:?} ({trigger:?})", A::NAME);
+    actor.on_exit(ctx, &previous).await;
+
+    let fsm = actor.fsm();
+    fsm.generation += 1;
+    fsm.state = next.clone();
+    fsm.entered = Instant::now();
+    fsm.record(Transition {
+        from: previous,
+        to: next.clone(),
+        trigger,
+        at: fsm.entered,
+    });
+
+    actor.on_enter(ctx, &next).await;
+    start_timer(actor, ctx);
+}
+
+/// Cancel the previous state's timeout and start the one of the current state.
+fn start_timer<A: FsmActor>(actor: &mut A, ctx: &mut Context<A>) {
+    if let Some(timer) = actor.fsm().timer.take() {
+        timer.abort();
+    }
+
+    let state = actor.fsm().state.clone();
+    let Some((after, next)) = actor.state_timeout(&state) else {
+        return;
+    };
+
+    let generation = actor.fsm().generation;
+    let myself = std::sync::Weak::clone(&ctx.weak_force_tx);
+    let timer = ctx.spawn_task(async move {
+        A::sleep(after).await;
+        let Some(tx) = myself.upgrade() else {
+            return;
+        };
+        let timeout = Payload::task(move |actor: &mut A, ctx: &mut Context<A>| {
+            Box::pin(async move {
+                // a transition might have happened while this was queued
+                if actor.fsm().generation == generation {
+                    change_state(actor, ctx, next, Trigger::Timeout).await;
+                }
+            })
+        });
+        if tx.send(timeout).is_err() {
+            log::trace!("{} stopped before its state timed out", A::NAME);
+        }
+    });
+    actor.fsm().timer = Some(timer);
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use crate::{DynResult, prelude::*};
+
+    use super::{Fsm, FsmActor, Trigger};
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Link {
+        Connecting,
+        Online,
+        Offline,
+    }
+
+    struct Client {
+        fsm: Fsm<Link>,
+        hooks: Vec<String>,
+    }
+
+    impl Client {
+        fn new() -> Self {
+            Self {
+                fsm: Fsm::new(Link::Connecting),
+                hooks: Vec::new(),
+            }
+        }
+    }
+
+    impl Actor for Client {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+            self.enter_initial_state(ctx).await;
+            Ok(())
+        }
+    }
+
+    impl FsmActor for Client {
+        type State = Link;
+
+        fn fsm(&mut self) -> &mut Fsm<Link> {
+            &mut self.fsm
+        }
+
+        async fn on_enter(&mut self, _: &mut Context<Self>, state: &Link) {
+            self.hooks.push(format!("enter {state:?}"));
+        }
+
+        async fn on_exit(&mut self, _: &mut Context<Self>, state: &Link) {
+            self.hooks.push(format!("exit {state:?}"));
+        }
+
+        fn state_timeout(&self, state: &Link) -> Option<(Duration, Link)> {
+            (*state == Link::Connecting).then_some((Duration::from_millis(50), Link::Offline))
+        }
+    }
+
+    struct Connected;
+    impl Message for Connected {
+        type Response = ();
+    }
+
+    struct Reconnect;
+    impl Message for Reconnect {
+        type Response = ();
+    }
+
+    impl Handler<Connected> for Client {
+        async fn handle(&mut self, ctx: &mut Context<Self>, _: Connected) {
+            self.transition(ctx, Link::Online).await;
+        }
+    }
+
+    impl Handler<Reconnect> for Client {
+        async fn handle(&mut self, ctx: &mut Context<Self>, _: Reconnect) {
+            self.transition(ctx, Link::Connecting).await;
+        }
+    }
+
+    fn transitions(client: &Client) -> Vec<(Link, Link, Trigger)> {
+        client
+            .fsm
+            .transitions()
+            .map(|transition| {
+                (
+                    transition.from.clone(),
+                    transition.to.clone(),
+                    transition.trigger,
+                )
+            })
+            .collect()
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn transitions_on_events() {
+        let addr = Client::new().spawn_owning();
+        addr.send(Connected).await.unwrap();
+
+        // the timeout of `Connecting` was canceled by the transition
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (client, _) = addr.stop_and_join().unwrap().await;
+        let client = client.unwrap();
+        assert_eq!(client.fsm.state(), &Link::Online);
+        assert_eq!(
+            client.hooks,
+            vec!["enter Connecting", "exit Connecting", "enter Online"]
+        );
+        assert_eq!(
+            transitions(&client),
+            vec![(Link::Connecting, Link::Online, Trigger::Event)]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn transitions_on_state_timeout() {
+        let addr = Client::new().spawn_owning();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        addr.send(Reconnect).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        addr.send(Connected).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (client, _) = addr.stop_and_join().unwrap().await;
+        let client = client.unwrap();
+        assert_eq!(client.fsm.state(), &Link::Online);
+        assert_eq!(
+            transitions(&client),
+            vec![
+                (Link::Connecting, Link::Offline, Trigger::Timeout),
+                (Link::Offline, Link::Connecting, Trigger::Event),
+                (Link::Connecting, Link::Online, Trigger::Event),
+            ]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn transition_log_is_bounded() {
+        let mut client = Client::new();
+        client.fsm = Fsm::with_log_capacity(Link::Connecting, 2);
+        let addr = client.spawn_owning();
+        for _ in 0..3 {
+            addr.send(Connected).await.unwrap();
+        }
+
+        let (client, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(
+            transitions(&client.unwrap()),
+            vec![
+                (Link::Online, Link::Online, Trigger::Event),
+                (Link::Online, Link::Online, Trigger::Event),
+            ]
+        );
+    }
+}
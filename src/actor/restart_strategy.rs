@@ -1,6 +1,15 @@
-use std::future::Future;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    hash::{BuildHasher as _, Hasher as _},
+    time::{Duration, Instant},
+};
 
-use crate::{Actor, DynResult, context::Context};
+use crate::{
+    Actor, DynResult, StopReason,
+    context::Context,
+    error::{ActorError, Result},
+};
 
 pub trait RestartStrategy<A: Actor> {
     fn refresh(actor: A, ctx: &mut Context<A>) -> impl Future<Output = DynResult<A>> + Send;
@@ -18,7 +27,7 @@ impl<A: Actor> RestartStrategy<A> for NonRestartable {
 pub struct RestartOnly;
 impl<A: Actor> RestartStrategy<A> for RestartOnly {
     async fn refresh(mut actor: A, ctx: &mut Context<A>) -> DynResult<A> {
-        actor.stopped(ctx).await;
+        actor.stopped(ctx, StopReason::Restarting).await;
         actor.started(ctx).await?;
         Ok(actor)
     }
@@ -29,8 +38,10 @@ pub struct RecreateFromDefault;
 impl<A: Actor + Default> RestartStrategy<A> for RecreateFromDefault {
     async fn refresh(mut actor: A, ctx: &mut Context<A>) -> DynResult<A> {
         eprintln!("recreating refresh");
-        actor.stopped(ctx).await;
+        actor.stopped(ctx, StopReason::Restarting).await;
         actor = A::default();
+        // the fresh actor starts without the behaviors of the old one
+        ctx.behaviors = Default::default();
         actor.started(ctx).await?;
         Ok(actor)
     }
@@ -38,3 +49,172 @@ impl<A: Actor + Default> RestartStrategy<A> for RecreateFromDefault {
 
 /// A marker trait for actors that can be restarted.
 pub trait RestartableActor: Actor {}
+
+/// Limits how often an actor may be restarted.
+///
+/// If the actor is restarted more than `max_restarts` times within `within`
+/// it gives up and stops with [`ActorError::TooManyRestarts`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RestartIntensity {
+    /// How many restarts are tolerated within the window.
+    pub max_restarts: usize,
+    /// The length of the window.
+    pub within: Duration,
+}
+
+/// How long to wait before restarting an actor.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Backoff {
+    /// Restart immediately.
+    #[default]
+    None,
+
+    /// Always wait for the same duration.
+    Fixed(Duration),
+
+    /// Double the delay with every restart, starting at `initial` and never exceeding `max`.
+    ///
+    /// With `jitter` enabled the delay is randomly picked between half and the full computed delay.
+    /// Restarts are counted within the [`RestartIntensity`] window if one is configured,
+    /// otherwise every restart counts.
+    Exponential {
+        initial: Duration,
+        max: Duration,
+        jitter: bool,
+    },
+}
+
+impl Backoff {
+    /// The delay before the `attempt`-th restart, starting at 1.
+    fn delay(&self, attempt: u32) -> Duration {
+        match *self {
+            Backoff::None => Duration::ZERO,
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential {
+                initial,
+                max,
+                jitter,
+            } => {
+                let factor = 1u32
+                    .checked_shl(attempt.saturating_sub(1))
+                    .unwrap_or(u32::MAX);
+                let delay = initial.saturating_mul(factor).min(max);
+                if jitter {
+                    let random = std::collections::hash_map::RandomState::new()
+                        .build_hasher()
+                        .finish();
+                    delay.mul_f64(0.5 + random as f64 / u64::MAX as f64 / 2.0)
+                } else {
+                    delay
+                }
+            }
+        }
+    }
+}
+
+/// Keeps track of recent restarts of an actor.
+#[derive(Debug, Default)]
+pub(crate) struct RestartTracker {
+    intensity: Option<RestartIntensity>,
+    backoff: Backoff,
+    recent: VecDeque<Instant>,
+    total: u32,
+}
+
+impl RestartTracker {
+    pub const fn new(intensity: Option<RestartIntensity>, backoff: Backoff) -> Self {
+        RestartTracker {
+            intensity,
+            backoff,
+            recent: VecDeque::new(),
+            total: 0,
+        }
+    }
+
+    pub const fn set_intensity(&mut self, intensity: RestartIntensity) {
+        self.intensity = Some(intensity);
+    }
+
+    pub const fn set_backoff(&mut self, backoff: Backoff) {
+        self.backoff = backoff;
+    }
+
+    /// Records a restart and returns how long to wait before performing it.
+    pub fn register(&mut self, now: Instant) -> Result<Duration> {
+        self.total = self.total.saturating_add(1);
+
+        let Some(RestartIntensity {
+            max_restarts,
+            within,
+        }) = self.intensity
+        else {
+            return Ok(self.backoff.delay(self.total));
+        };
+
+        while self
+            .recent
+            .front()
+            .is_some_and(|restart| now.duration_since(*restart) >= within)
+        {
+            self.recent.pop_front();
+        }
+
+        if self.recent.len() >= max_restarts {
+            return Err(ActorError::TooManyRestarts);
+        }
+        self.recent.push_back(now);
+
+        let attempt = u32::try_from(self.recent.len()).unwrap_or(u32::MAX);
+        Ok(self.backoff.delay(attempt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn exponential_backoff_is_capped() {
+        let backoff = Backoff::Exponential {
+            initial: MS,
+            max: MS * 5,
+            jitter: false,
+        };
+        let delays = (1..=5).map(|attempt| backoff.delay(attempt));
+        assert!(delays.eq([MS, MS * 2, MS * 4, MS * 5, MS * 5]));
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let backoff = Backoff::Exponential {
+            initial: MS * 100,
+            max: MS * 100,
+            jitter: true,
+        };
+        for _ in 0..100 {
+            let delay = backoff.delay(1);
+            assert!(delay >= MS * 50 && delay <= MS * 100, "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn intensity_window_expires() {
+        let intensity = RestartIntensity {
+            max_restarts: 2,
+            within: MS * 10,
+        };
+        let mut tracker = RestartTracker::new(Some(intensity), Backoff::Fixed(MS));
+        let start = Instant::now();
+
+        assert_eq!(tracker.register(start), Ok(MS));
+        assert_eq!(tracker.register(start + MS), Ok(MS));
+        assert_eq!(
+            tracker.register(start + MS * 2),
+            Err(ActorError::TooManyRestarts)
+        );
+        assert_eq!(tracker.register(start + MS * 11), Ok(MS));
+    }
+}
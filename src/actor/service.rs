@@ -199,7 +199,7 @@ mod tests {
     #[cfg(feature = "tokio")]
     mod spawned_with_tokio {
         use crate::{
-            Service,
+            Service, StopReason,
             actor::tests::{Identify, Ping, spawned_with_tokio::TokioActor},
             prelude::Spawnable as _,
             spawner::{SpawnableWith, TokioSpawner},
@@ -225,7 +225,7 @@ mod tests {
             svc_addr.call(Ping).await.unwrap();
 
             svc_addr.stop().unwrap();
-            svc_addr.await.unwrap();
+            assert_eq!(svc_addr.await, StopReason::Stopped);
         }
 
         #[test_log::test(tokio::test)]
@@ -250,7 +250,7 @@ mod tests {
 
             first_svc_again.stop().unwrap();
             assert!(!first_svc_again.stopped());
-            first_svc_again.await.unwrap();
+            assert_eq!(first_svc_again.await, StopReason::Stopped);
 
             // register a new service instance
             let (second_svc, replaced_first) = Svc::new(1338).spawn().register().await.unwrap();
@@ -266,7 +266,7 @@ mod tests {
     #[cfg(feature = "async-std")]
     mod spawned_with_asyncstd {
         use crate::{
-            Service,
+            Service, StopReason,
             actor::tests::{Identify, Ping, spawned_with_asyncstd::AsyncStdActor},
             spawner::{AsyncStdSpawner, SpawnableWith},
         };
@@ -294,7 +294,7 @@ mod tests {
             svc_addr.call(Ping).await.unwrap();
 
             svc_addr.stop().unwrap();
-            svc_addr.await.unwrap();
+            assert_eq!(svc_addr.await, StopReason::Stopped);
         }
 
         #[async_std::test]
@@ -305,7 +305,7 @@ mod tests {
 
             svc_addr.call(Ping).await.unwrap();
             svc_addr.stop().unwrap();
-            svc_addr.await.unwrap();
+            assert_eq!(svc_addr.await, StopReason::Stopped);
         }
     }
 }
@@ -186,6 +186,7 @@ mod tests {
     #[cfg(feature = "tokio")]
     mod spawned_with_tokio {
         use crate::{
+            StopReason,
             actor::tests::{Ping, spawned_with_tokio::TokioActor},
             spawner::{DefaultSpawnable, Spawnable, TokioSpawner},
         };
@@ -198,7 +199,7 @@ mod tests {
 
             addr.call(Ping).await.unwrap();
             addr.stop().unwrap();
-            addr.await.unwrap()
+            assert_eq!(addr.await, StopReason::Stopped);
         }
 
         #[tokio::test]
@@ -209,13 +210,14 @@ mod tests {
 
             addr.call(Ping).await.unwrap();
             addr.stop().unwrap();
-            addr.await.unwrap()
+            assert_eq!(addr.await, StopReason::Stopped);
         }
     }
 
     #[cfg(feature = "async-std")]
     mod spawned_with_asyncstd {
         use crate::{
+            StopReason,
             actor::tests::{Ping, spawned_with_asyncstd::AsyncStdActor},
             spawner::{AsyncStdSpawner, DefaultSpawnable, Spawnable},
         };
@@ -228,7 +230,7 @@ mod tests {
 
             addr.call(Ping).await.unwrap();
             addr.stop().unwrap();
-            addr.await.unwrap()
+            assert_eq!(addr.await, StopReason::Stopped);
         }
 
         #[async_std::test]
@@ -239,7 +241,7 @@ mod tests {
 
             addr.call(Ping).await.unwrap();
             addr.stop().unwrap();
-            addr.await.unwrap()
+            assert_eq!(addr.await, StopReason::Stopped);
         }
     }
 }
@@ -0,0 +1,82 @@
+use crate::{context::ContextID, error::ActorError};
+
+/// Why an actor stopped.
+///
+/// The reason is passed to [`Actor::stopped`](`crate::Actor::stopped`),
+/// returned when awaiting an [`Addr`](`crate::Addr`) and when joining an [`OwningAddr`](`crate::OwningAddr`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The actor was stopped via [`Addr::stop`](`crate::Addr::stop`) or [`Context::stop`](`crate::Context::stop`).
+    Stopped,
+
+    /// All strong addresses to the actor were dropped.
+    AddrsDropped,
+
+    /// The stream the actor was attached to has ended.
+    StreamFinished,
+
+    /// The actor is about to be restarted.
+    ///
+    /// This is only ever passed to [`Actor::stopped`](`crate::Actor::stopped`).
+    Restarting,
+
+    /// [`Actor::started`](`crate::Actor::started`) returned an error.
+    StartFailed(String),
+
+    /// A handler took too long and the actor was configured to fail on timeout.
+    Timeout,
+
+    /// A handler panicked.
+    Panicked(String),
+
+    /// The actor was restarted more often than its [`RestartIntensity`](`crate::RestartIntensity`) allows.
+    TooManyRestarts,
+
+    /// An actor [linked](`crate::Context::link`) to this one failed.
+    LinkFailed(ContextID),
+
+    /// The actor's task ended without reporting a reason, e.g. because it was aborted.
+    Canceled,
+}
+
+impl StopReason {
+    /// Returns `true` if the actor did not shut down cleanly.
+    pub const fn is_failure(&self) -> bool {
+        !matches!(
+            self,
+            StopReason::Stopped
+                | StopReason::AddrsDropped
+                | StopReason::StreamFinished
+                | StopReason::Restarting
+        )
+    }
+
+    /// The error the actor's event loop ends with, if any.
+    pub(crate) fn to_error(&self) -> Option<ActorError> {
+        match self {
+            StopReason::Timeout => Some(ActorError::Timeout),
+            StopReason::Panicked(message) => Some(ActorError::Panicked(message.clone())),
+            StopReason::TooManyRestarts => Some(ActorError::TooManyRestarts),
+            StopReason::LinkFailed(id) => Some(ActorError::LinkFailed(*id)),
+            StopReason::Canceled => Some(ActorError::Canceled(futures::channel::oneshot::Canceled)),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::Stopped => write!(f, "stopped"),
+            StopReason::AddrsDropped => write!(f, "all addresses dropped"),
+            StopReason::StreamFinished => write!(f, "stream finished"),
+            StopReason::Restarting => write!(f, "restarting"),
+            StopReason::StartFailed(error) => write!(f, "failed to start: {error}"),
+            StopReason::Timeout => write!(f, "handler timed out"),
+            StopReason::Panicked(message) => write!(f, "handler panicked: {message}"),
+            StopReason::TooManyRestarts => write!(f, "restarted too often"),
+            StopReason::LinkFailed(id) => write!(f, "linked actor {id} failed"),
+            StopReason::Canceled => write!(f, "canceled"),
+        }
+    }
+}
@@ -1,22 +1,39 @@
-use futures::{FutureExt, channel::oneshot};
-use std::{future::Future, pin::Pin, sync::Arc, task::Poll};
+use futures::{FutureExt, Stream, channel::oneshot};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::Poll,
+    time::{Duration, Instant},
+};
 use weak_addr::WeakAddr;
 
+mod call_handle;
+mod call_stream;
 pub mod caller;
+mod conflate;
+mod scatter_gather;
 pub mod sender;
+mod sink;
 pub mod weak_addr;
 pub mod weak_caller;
 pub mod weak_sender;
 
+pub use call_handle::CallHandle;
+pub(crate) use call_handle::Cancellation;
+pub use conflate::{Conflate, ConflationKey};
+pub use scatter_gather::ScatterGather;
+pub use sink::ActorSink;
+
 use crate::{
-    RestartableActor,
+    RestartableActor, StopReason,
     actor::Actor,
-    channel::{ChanTx, ForceChanTx},
-    context::{ContextID, RunningFuture},
-    environment::Payload,
-    error::Result,
+    channel::{ChanTx, ForceChanTx, MailboxStats, MailboxStatsFn},
+    context::{ContextID, RunningFuture, behavior},
+    environment::{Envelope, PanicSlot, Payload},
+    error::{ActorError, Result},
     handler::Handler,
-    spawner::{ActorHandle, JoinFuture},
+    spawner::ActorHandle,
 };
 
 /// Anything that you want to send to an actor.
@@ -38,6 +55,22 @@ use crate::{
 /// struct Store(&'static str);
 /// ```
 ///
+/// Messages can also declare a [`Priority`].
+///
+/// ```rust
+/// # use hannibal::message;
+/// #[message(priority = High)]
+/// struct Flush;
+/// ```
+///
+/// Or a [deadline](`Message::DEADLINE`), after which they are no longer handled.
+///
+/// ```rust
+/// # use hannibal::message;
+/// #[message(deadline_ms = 500)]
+/// struct Quote;
+/// ```
+///
 /// You can also derive the `Message` trait for simple messages without a response.
 ///
 /// ```rust
@@ -49,6 +82,43 @@ use crate::{
 pub trait Message: 'static + Send {
     /// What the actor should respond with.
     type Response: 'static + Send;
+
+    /// How urgently the message should be handled.
+    ///
+    /// Only taken into account by actors with a [priority mailbox](`crate::build`).
+    const PRIORITY: Priority = Priority::Normal;
+
+    /// How long after sending the message is still worth handling.
+    ///
+    /// Messages that are not handled within this time are skipped and reported as
+    /// [expired](`crate::DeadLetterReason::Expired`), so is a message whose handler runs past it.
+    /// Unlike the actor's [`timeout`](`crate::build`) this never restarts or stops the actor,
+    /// callers get [`ActorError::DeadlineExceeded`](`crate::error::ActorError::DeadlineExceeded`).
+    /// Senders can set a deadline for a single message too, see [`Addr::send_with_deadline`].
+    const DEADLINE: Option<Duration> = None;
+
+    /// Which queued messages this one replaces in a [conflating mailbox](`crate::build`).
+    ///
+    /// Messages that implement [`Conflate`] return [`ConflationKey::of`], see there for an example.
+    fn conflation_key(&self) -> Option<ConflationKey> {
+        None
+    }
+}
+
+/// How urgently a message should be handled.
+///
+/// Actors with a priority mailbox always handle messages with a higher priority first,
+/// messages of the same priority are handled in the order they were sent.
+/// Other mailboxes ignore the priority.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Handled after all other messages.
+    Low,
+    /// The default priority.
+    #[default]
+    Normal,
+    /// Handled before all other messages.
+    High,
 }
 
 impl Message for () {
@@ -69,6 +139,7 @@ pub struct Addr<A> {
     pub(crate) context_id: ContextID,
     pub(crate) payload_tx: ChanTx<A>,
     pub(crate) payload_force_tx: ForceChanTx<A>,
+    pub(crate) mailbox: MailboxStatsFn,
     pub(crate) running: RunningFuture,
 }
 
@@ -78,20 +149,37 @@ impl<A: Actor> Clone for Addr<A> {
             context_id: self.context_id,
             payload_tx: Arc::clone(&self.payload_tx),
             payload_force_tx: Arc::clone(&self.payload_force_tx),
+            mailbox: Arc::clone(&self.mailbox),
             running: self.running.clone(),
         }
     }
 }
 
 impl<A: Actor> Addr<A> {
+    /// The id of the actor's [`Context`](`crate::Context`).
+    pub const fn id(&self) -> ContextID {
+        self.context_id
+    }
+
+    /// How many messages are waiting to be handled by the actor.
+    pub fn mailbox_len(&self) -> usize {
+        (self.mailbox)().len
+    }
+
+    /// A snapshot of the actor's mailbox, e.g. to detect slow consumers.
+    pub fn mailbox_stats(&self) -> MailboxStats {
+        (self.mailbox)()
+    }
+
     pub fn stop(&mut self) -> Result<()> {
         self.payload_force_tx.send(Payload::Stop)?;
         Ok(())
     }
 
-    pub async fn stop_and_join(mut self) -> Result<()> {
+    /// Stops the actor and waits for it to stop.
+    pub async fn stop_and_join(mut self) -> Result<StopReason> {
         self.stop()?;
-        self.await
+        Ok(self.await)
     }
 
     pub fn running(&self) -> bool {
@@ -107,26 +195,95 @@ impl<A: Actor> Addr<A> {
         A: Handler<M>,
     {
         let (tx_response, response) = oneshot::channel();
-        self.payload_force_tx
-            .send(Payload::task(move |actor, ctx| {
-                Box::pin(async move {
-                    let res = Handler::handle(actor, ctx, msg).await;
-                    let _ = tx_response.send(res);
-                })
-            }))?;
+        let envelope = Envelope::call::<M>();
+        let (deadline, panic) = (envelope.deadline, envelope.panic.clone());
+        self.payload_tx
+            .send(Payload::call(envelope, msg, tx_response))
+            .await?;
 
-        Ok(response.await?)
+        await_response(response, deadline, panic).await
+    }
+
+    /// Call the actor, the message is not handled if the returned handle is dropped before the actor gets to it.
+    ///
+    /// Nothing is sent until the handle is awaited.
+    pub fn call_cancellable<M: Message>(&self, msg: M) -> CallHandle<M::Response>
+    where
+        A: Handler<M>,
+    {
+        let cancellation = Cancellation::default();
+        let envelope = Envelope {
+            cancellation: Some(cancellation.clone()),
+            ..Envelope::call::<M>()
+        };
+        let payload_tx = Arc::clone(&self.payload_tx);
+        CallHandle::new(cancellation, async move {
+            let (tx_response, response) = oneshot::channel();
+            let (deadline, panic) = (envelope.deadline, envelope.panic.clone());
+            payload_tx
+                .send(Payload::call(envelope, msg, tx_response))
+                .await?;
+            await_response(response, deadline, panic).await
+        })
+    }
+
+    /// Call the actor and wait at most `timeout` for the response.
+    ///
+    /// Fails with [`ActorError::CallTimeout`](`crate::error::ActorError::CallTimeout`),
+    /// the message is then not handled unless the actor already started handling it.
+    pub async fn call_timeout<M: Message>(&self, msg: M, timeout: Duration) -> Result<M::Response>
+    where
+        A: Handler<M>,
+    {
+        self.call_cancellable(msg).timeout(timeout).await
+    }
+
+    /// Call the actor with every message of `stream` and yield the responses in the same order.
+    ///
+    /// Up to `max_in_flight` calls are pipelined.
+    /// Every response carries its own error, the stream ends once the actor stopped.
+    /// The stream does not keep the actor alive.
+    pub fn call_stream<M, S>(
+        &self,
+        stream: S,
+        max_in_flight: usize,
+    ) -> impl Stream<Item = Result<M::Response>> + Send + 'static
+    where
+        A: Handler<M>,
+        M: Message,
+        S: Stream<Item = M> + Send + 'static,
+    {
+        let weak = self.downgrade();
+        let running = self.running.clone();
+        call_stream::call_stream(
+            stream,
+            max_in_flight,
+            move |msg| {
+                let addr = weak.upgrade();
+                async move {
+                    match addr {
+                        Some(addr) => addr.call(msg).await,
+                        None => Err(ActorError::AlreadyStopped),
+                    }
+                }
+            },
+            move || running.clone().now_or_never().is_some(),
+        )
     }
 
     /// Ping the actor to check if it is already/still alive.
     pub async fn ping(&self) -> Result<()> {
         let (tx_response, response) = oneshot::channel();
-        self.payload_force_tx
-            .send(Payload::task(move |_actor, _ctx| {
-                Box::pin(async move {
-                    let _ = tx_response.send(());
-                })
-            }))?;
+        self.payload_tx
+            .send(Payload::message(
+                Envelope::new::<()>(),
+                move |_actor, _ctx| {
+                    Box::pin(async move {
+                        let _ = tx_response.send(());
+                    })
+                },
+            ))
+            .await?;
 
         Ok(response.await?)
     }
@@ -138,9 +295,7 @@ impl<A: Actor> Addr<A> {
         A: Handler<M>,
     {
         self.payload_force_tx
-            .send(Payload::task(move |actor, ctx| {
-                Box::pin(Handler::handle(actor, ctx, msg))
-            }))?;
+            .send(Payload::handle(Envelope::of(&msg), msg))?;
         Ok(())
     }
 
@@ -148,14 +303,105 @@ impl<A: Actor> Addr<A> {
     where
         A: Handler<M>,
     {
+        self.send_with_priority(msg, M::PRIORITY).await
+    }
+
+    /// Send a message with a different priority than [`Message::PRIORITY`].
+    pub async fn send_with_priority<M: Message<Response = ()>>(
+        &self,
+        msg: M,
+        priority: Priority,
+    ) -> Result<()>
+    where
+        A: Handler<M>,
+    {
+        let envelope = Envelope {
+            priority,
+            ..Envelope::of(&msg)
+        };
+        self.payload_tx.send(Payload::handle(envelope, msg)).await?;
+        Ok(())
+    }
+
+    /// Send a message that is skipped if the actor does not get to it before `deadline`.
+    ///
+    /// The handler is abandoned if it runs past the deadline, see [`Message::DEADLINE`].
+    pub async fn send_with_deadline<M: Message<Response = ()>>(
+        &self,
+        msg: M,
+        deadline: Instant,
+    ) -> Result<()>
+    where
+        A: Handler<M>,
+    {
+        let envelope = Envelope {
+            deadline: Some(deadline),
+            ..Envelope::of(&msg)
+        };
+        self.payload_tx.send(Payload::handle(envelope, msg)).await?;
+        Ok(())
+    }
+
+    /// Call the actor with a deadline, see [`send_with_deadline`](`Addr::send_with_deadline`).
+    ///
+    /// Fails with [`ActorError::DeadlineExceeded`](`crate::error::ActorError::DeadlineExceeded`)
+    /// if the message was skipped or its handler timed out.
+    pub async fn call_with_deadline<M: Message>(
+        &self,
+        msg: M,
+        deadline: Instant,
+    ) -> Result<M::Response>
+    where
+        A: Handler<M>,
+    {
+        let (tx_response, response) = oneshot::channel();
+        let envelope = Envelope {
+            deadline: Some(deadline),
+            ..Envelope::call::<M>()
+        };
+        let panic = envelope.panic.clone();
         self.payload_tx
-            .send(Payload::task(move |actor, ctx| {
-                Box::pin(Handler::handle(actor, ctx, msg))
-            }))
+            .send(Payload::call(envelope, msg, tx_response))
+            .await?;
+
+        await_response(response, Some(deadline), panic).await
+    }
+
+    /// Send a message that only the actor's [`Behavior`](`crate::Behavior`)s handle.
+    ///
+    /// The actor does not have to implement [`Handler<M>`] itself,
+    /// if the active behavior does not handle the message it is reported as a [`DeadLetter`](`crate::DeadLetter`).
+    pub async fn send_to_behavior<M: Message<Response = ()>>(&self, msg: M) -> Result<()> {
+        self.payload_tx
+            .send(Payload::handle_with(
+                Envelope::of(&msg),
+                msg,
+                behavior::dispatch_to_behaviors,
+            ))
             .await?;
         Ok(())
     }
 
+    /// Call the actor with a message that only its [`Behavior`](`crate::Behavior`)s handle,
+    /// see [`send_to_behavior`](`Addr::send_to_behavior`).
+    ///
+    /// Fails with [`ActorError::Canceled`](`crate::error::ActorError::Canceled`) if the active behavior does not handle the message.
+    pub async fn call_behavior<M: Message>(&self, msg: M) -> Result<M::Response> {
+        let (tx_response, response) = oneshot::channel();
+        let envelope = Envelope::call::<M>();
+        let (deadline, panic) = (envelope.deadline, envelope.panic.clone());
+        self.payload_tx
+            .send(Payload::call_with(
+                envelope,
+                msg,
+                tx_response,
+                behavior::dispatch_to_behaviors,
+            ))
+            .await?;
+
+        await_response(response, deadline, panic).await
+    }
+
     pub fn downgrade(&self) -> WeakAddr<A> {
         WeakAddr::from(self)
     }
@@ -174,6 +420,34 @@ impl<A: Actor> Addr<A> {
         weak_sender::WeakSender::from(self.to_owned())
     }
 
+    /// A [`Sink`](`futures::Sink`) for messages of type `M`.
+    ///
+    /// ```no_run
+    /// # use futures::{StreamExt, stream};
+    /// # use hannibal::prelude::*;
+    /// # #[derive(Message)]
+    /// # struct Item(u32);
+    /// # #[derive(Default)]
+    /// # struct Collector;
+    /// # impl Actor for Collector {}
+    /// # impl Handler<Item> for Collector {
+    /// #     async fn handle(&mut self, _: &mut Context<Self>, _: Item) {}
+    /// # }
+    /// # async fn forward(addr: Addr<Collector>) {
+    /// stream::iter(0..10)
+    ///     .map(|n| Ok(Item(n)))
+    ///     .forward(addr.sink::<Item>())
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub fn sink<M: Message<Response = ()>>(&self) -> ActorSink<M>
+    where
+        A: Handler<M>,
+    {
+        ActorSink::new(self.sender())
+    }
+
     pub fn caller<M: Message>(&self) -> caller::Caller<M>
     where
         A: Handler<M>,
@@ -189,23 +463,48 @@ impl<A: Actor> Addr<A> {
     }
 }
 
+/// Wait for the response to a call.
+///
+/// A call with a [deadline](`Message::DEADLINE`) whose message was skipped or whose handler ran out of time
+/// fails with [`ActorError::DeadlineExceeded`].
+pub(crate) async fn await_response<R>(
+    response: oneshot::Receiver<R>,
+    deadline: Option<Instant>,
+    panic: Option<PanicSlot>,
+) -> Result<R> {
+    match response.await {
+        Ok(response) => Ok(response),
+        Err(canceled) => {
+            if let Some(message) = panic.as_ref().and_then(PanicSlot::get) {
+                Err(ActorError::Panicked(message.to_owned()))
+            } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                Err(ActorError::DeadlineExceeded)
+            } else {
+                Err(canceled.into())
+            }
+        }
+    }
+}
+
 impl<A: RestartableActor> Addr<A> {
     /// Restart the actor. This is not possible for all actors.
     ///
-    /// [`StreamHandlers`](`crate::StreamHandler`) for example can't be restarted.
+    /// [`StreamHandlers`](`crate::StreamHandler`) can only be restarted if they were built
+    /// with a stream factory, otherwise the restart is ignored.
     pub fn restart(&mut self) -> Result<()> {
         self.payload_force_tx.send(Payload::Restart)?;
         Ok(())
     }
 }
 
+/// Resolves to the [`StopReason`] once the actor has stopped.
 impl<A> Future for Addr<A> {
-    type Output = Result<()>;
+    type Output = StopReason;
     fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
         self.get_mut()
             .running
             .poll_unpin(cx)
-            .map(|p| p.map_err(Into::into))
+            .map(|reason| reason.unwrap_or(StopReason::Canceled))
     }
 }
 
@@ -225,17 +524,22 @@ impl<A: Actor> OwningAddr<A> {
         OwningAddr { addr, handle }
     }
 
-    /// Waits for the actor to stop and returns it.
-    pub fn join(&mut self) -> JoinFuture<A> {
-        self.handle.join()
+    /// Waits for the actor to stop and returns it together with the reason why it stopped.
+    ///
+    /// The actor is only returned if its event loop did not fail.
+    pub async fn join(&mut self) -> (Option<A>, StopReason) {
+        let actor = self.handle.join().await;
+        let reason = self.addr.running.clone().await;
+        let reason = reason.unwrap_or(StopReason::Canceled);
+        (actor, reason)
     }
 
     /// Stops the actor and waits for it to stop.
     ///
     /// If stop fails you will get an error before waiting for the actor to stop.
-    pub fn stop_and_join(mut self) -> Result<JoinFuture<A>> {
+    pub fn stop_and_join(mut self) -> Result<impl Future<Output = (Option<A>, StopReason)> + Send> {
         self.addr.stop()?;
-        Ok(self.join())
+        Ok(async move { self.join().await })
     }
 
     /// Stops the actor and returns it.
@@ -243,6 +547,7 @@ impl<A: Actor> OwningAddr<A> {
         self.addr.stop()?;
         self.join()
             .await
+            .0
             .ok_or(crate::error::ActorError::AlreadyStopped)
     }
 
@@ -262,6 +567,22 @@ impl<A: Actor> OwningAddr<A> {
         self.addr.call(msg).await
     }
 
+    /// See [`Addr::call_cancellable`].
+    pub fn call_cancellable<M: Message>(&self, msg: M) -> CallHandle<M::Response>
+    where
+        A: Handler<M>,
+    {
+        self.addr.call_cancellable(msg)
+    }
+
+    /// See [`Addr::call_timeout`].
+    pub async fn call_timeout<M: Message>(&self, msg: M, timeout: Duration) -> Result<M::Response>
+    where
+        A: Handler<M>,
+    {
+        self.addr.call_timeout(msg, timeout).await
+    }
+
     pub async fn send<M: Message<Response = ()>>(&self, msg: M) -> Result<()>
     where
         A: Handler<M>,
@@ -269,6 +590,62 @@ impl<A: Actor> OwningAddr<A> {
         self.addr.send(msg).await
     }
 
+    /// Send a message with a different priority than [`Message::PRIORITY`].
+    pub async fn send_with_priority<M: Message<Response = ()>>(
+        &self,
+        msg: M,
+        priority: Priority,
+    ) -> Result<()>
+    where
+        A: Handler<M>,
+    {
+        self.addr.send_with_priority(msg, priority).await
+    }
+
+    /// See [`Addr::send_with_deadline`].
+    pub async fn send_with_deadline<M: Message<Response = ()>>(
+        &self,
+        msg: M,
+        deadline: Instant,
+    ) -> Result<()>
+    where
+        A: Handler<M>,
+    {
+        self.addr.send_with_deadline(msg, deadline).await
+    }
+
+    /// See [`Addr::call_with_deadline`].
+    pub async fn call_with_deadline<M: Message>(
+        &self,
+        msg: M,
+        deadline: Instant,
+    ) -> Result<M::Response>
+    where
+        A: Handler<M>,
+    {
+        self.addr.call_with_deadline(msg, deadline).await
+    }
+
+    /// See [`Addr::send_to_behavior`].
+    pub async fn send_to_behavior<M: Message<Response = ()>>(&self, msg: M) -> Result<()> {
+        self.addr.send_to_behavior(msg).await
+    }
+
+    /// See [`Addr::call_behavior`].
+    pub async fn call_behavior<M: Message>(&self, msg: M) -> Result<M::Response> {
+        self.addr.call_behavior(msg).await
+    }
+
+    /// How many messages are waiting to be handled by the actor.
+    pub fn mailbox_len(&self) -> usize {
+        self.addr.mailbox_len()
+    }
+
+    /// A snapshot of the actor's mailbox, see [`Addr::mailbox_stats`].
+    pub fn mailbox_stats(&self) -> MailboxStats {
+        self.addr.mailbox_stats()
+    }
+
     pub fn to_addr(&self) -> Addr<A> {
         self.addr.clone()
     }
@@ -364,7 +741,7 @@ mod tests {
         tokio::spawn(event_loop);
         let addr2 = addr.clone();
         addr.stop().unwrap();
-        addr.await.unwrap();
+        assert_eq!(addr.await, StopReason::Stopped);
         assert!(addr2.send(Store("password")).await.is_err());
     }
 
@@ -376,8 +753,8 @@ mod tests {
         let addr2 = addr.clone();
         addr.stop().unwrap();
 
-        addr2.await.unwrap();
-        addr.await.unwrap();
+        assert_eq!(addr2.await, StopReason::Stopped);
+        assert_eq!(addr.await, StopReason::Stopped);
     }
 
     #[test_log::test(tokio::test)]
@@ -388,8 +765,8 @@ mod tests {
         let addr2 = addr.clone();
         addr.send(Stop).await.unwrap();
 
-        addr2.await.unwrap();
-        addr.await.unwrap();
+        assert_eq!(addr2.await, StopReason::Stopped);
+        assert_eq!(addr.await, StopReason::Stopped);
     }
 
     #[test_log::test(tokio::test)]
@@ -402,7 +779,7 @@ mod tests {
 
         addr.send(Stop).await.unwrap();
 
-        addr.await.unwrap();
+        assert_eq!(addr.await, StopReason::Stopped);
         assert!(addr2.stopped(), "addr2 should be stopped");
     }
 
@@ -0,0 +1,152 @@
+use futures::future::{self, Either};
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use crate::error::{ActorError, Result};
+
+/// Shared between a [`CallHandle`] and the message it waits for.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// A call that can be abandoned, see [`Addr::call_cancellable`](`crate::Addr::call_cancellable`).
+///
+/// Await the handle to get the response.
+/// If the handle is dropped, [cancelled](`CallHandle::cancel`) or [times out](`CallHandle::timeout`)
+/// before the actor started handling the message, the message is removed from the mailbox and never handled.
+/// A handler that already started runs to completion, its response is discarded.
+#[must_use = "dropping the handle cancels the call"]
+pub struct CallHandle<R> {
+    response: Pin<Box<dyn Future<Output = Result<R>> + Send>>,
+    cancellation: Cancellation,
+}
+
+impl<R> CallHandle<R> {
+    pub(crate) fn new(
+        cancellation: Cancellation,
+        response: impl Future<Output = Result<R>> + Send + 'static,
+    ) -> Self {
+        Self {
+            response: Box::pin(response),
+            cancellation,
+        }
+    }
+
+    /// Give up on the call.
+    pub fn cancel(self) {
+        drop(self);
+    }
+
+    /// Wait at most `timeout` for the response, fails with [`ActorError::CallTimeout`] and cancels the call otherwise.
+    pub async fn timeout(self, timeout: Duration) -> Result<R> {
+        match future::select(self, futures_timer::Delay::new(timeout)).await {
+            Either::Left((response, _)) => response,
+            Either::Right(((), _cancelled)) => Err(ActorError::CallTimeout),
+        }
+    }
+}
+
+impl<R> Future for CallHandle<R> {
+    type Output = Result<R>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.response.as_mut().poll(cx)
+    }
+}
+
+impl<R> Drop for CallHandle<R> {
+    fn drop(&mut self) {
+        self.cancellation.cancel();
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use crate::{Caller, error::ActorError, prelude::*};
+
+    #[derive(Debug, Default)]
+    struct Slow(Vec<u32>);
+    impl Actor for Slow {}
+
+    struct Work(u32);
+    impl Message for Work {
+        type Response = u32;
+    }
+
+    impl Handler<Work> for Slow {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, Work(n): Work) -> u32 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.0.push(n);
+            n
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn call_timeout_skips_pending_message() {
+        let addr = Slow::default().spawn_owning();
+        let busy = addr.call_cancellable(Work(1));
+        let busy = tokio::spawn(busy);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert_eq!(
+            addr.call_timeout(Work(2), Duration::from_millis(10)).await,
+            Err(ActorError::CallTimeout)
+        );
+        assert_eq!(busy.await.unwrap(), Ok(1));
+        assert_eq!(
+            addr.call_timeout(Work(3), Duration::from_millis(100)).await,
+            Ok(3)
+        );
+
+        let (slow, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(slow.unwrap().0, vec![1, 3]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn dropped_handle_cancels_call() {
+        let addr = Slow::default().spawn_owning();
+        let caller: Caller<Work> = addr.to_addr().caller();
+        let busy = tokio::spawn(caller.call_cancellable(Work(1)));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let mut cancelled = caller.call_cancellable(Work(2));
+        assert!(futures::poll!(&mut cancelled).is_pending());
+        cancelled.cancel();
+        let mut dropped = caller.call_cancellable(Work(3));
+        assert!(futures::poll!(&mut dropped).is_pending());
+        drop(dropped);
+        assert_eq!(busy.await.unwrap(), Ok(1));
+        assert_eq!(
+            caller
+                .downgrade()
+                .try_call_timeout(Work(4), Duration::from_millis(100))
+                .await,
+            Ok(4)
+        );
+
+        let (slow, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(slow.unwrap().0, vec![1, 4]);
+    }
+}
@@ -0,0 +1,124 @@
+//! Pushing a stream of messages through an actor.
+use futures::{Stream, StreamExt as _, future};
+
+use std::future::Future;
+
+use crate::error::{ActorError, Result};
+
+/// Call with every item of `stream`, at most `max_in_flight` at once, and yield the responses in order.
+///
+/// Ends after the first response that shows the actor won't answer any further calls:
+/// [`ActorError::AlreadyStopped`], or [`ActorError::Canceled`] and [`ActorError::Panicked`] for a call that was queued when the actor `stopped`.
+pub(crate) fn call_stream<S, F, Fut, R>(
+    stream: S,
+    max_in_flight: usize,
+    call: F,
+    stopped: impl Fn() -> bool + Send + 'static,
+) -> impl Stream<Item = Result<R>> + Send + 'static
+where
+    S: Stream + Send + 'static,
+    F: FnMut(S::Item) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<R>> + Send + 'static,
+    R: Send + 'static,
+{
+    stream
+        .map(call)
+        .buffered(max_in_flight.max(1))
+        .scan(false, move |ended, response| {
+            let response = (!*ended).then(|| {
+                *ended = match response {
+                    Err(ActorError::AlreadyStopped) => true,
+                    Err(ActorError::Canceled(_) | ActorError::Panicked(_)) => stopped(),
+                    _ => false,
+                };
+                response
+            });
+            future::ready(response)
+        })
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use futures::{StreamExt as _, stream};
+
+    use crate::{Caller, error::ActorError, prelude::*};
+
+    #[derive(Debug, Default)]
+    struct Doubler;
+    impl Actor for Doubler {}
+
+    struct Double(u32);
+    impl Message for Double {
+        type Response = u32;
+    }
+
+    impl Handler<Double> for Doubler {
+        async fn handle(&mut self, ctx: &mut Context<Self>, Double(n): Double) -> u32 {
+            tokio::time::sleep(Duration::from_millis(u64::from(n % 3))).await;
+            if n == 5 {
+                ctx.stop().unwrap();
+            }
+            n * 2
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn yields_responses_in_order() {
+        let addr = Doubler.spawn();
+        let mailbox = addr.clone();
+        let messages = stream::iter(6..16).map(Double).inspect(move |_| {
+            assert!(mailbox.mailbox_len() <= 3, "at most 3 calls in flight");
+        });
+
+        let responses = addr.call_stream(messages, 3).collect::<Vec<_>>().await;
+        assert_eq!(responses, (6..16).map(|n| Ok(n * 2)).collect::<Vec<_>>());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn ends_when_actor_stops() {
+        let addr = Doubler.spawn();
+        let caller: Caller<Double> = addr.caller();
+        let responses = caller
+            .call_stream(stream::iter(0..100).map(Double), 4)
+            .collect::<Vec<_>>()
+            .await;
+
+        let answered = responses
+            .iter()
+            .map_while(|response| response.as_ref().ok().copied())
+            .collect::<Vec<_>>();
+        assert!(answered.len() >= 6);
+        assert_eq!(
+            answered,
+            (0..)
+                .map(|n| n * 2)
+                .take(answered.len())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            responses.len(),
+            answered.len() + 1,
+            "stream must end once the actor stopped"
+        );
+        assert!(matches!(
+            responses.last(),
+            Some(Err(ActorError::Canceled(_) | ActorError::AlreadyStopped))
+        ));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn does_not_keep_actor_alive() {
+        let addr = Doubler.spawn();
+        let weak = addr.downgrade();
+        let responses = addr.call_stream(stream::pending::<Double>(), 2);
+
+        drop(addr);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(weak.upgrade().is_none());
+        drop(responses);
+    }
+}
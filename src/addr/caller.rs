@@ -1,12 +1,20 @@
 use dyn_clone::DynClone;
-use futures::channel::oneshot;
+use futures::{FutureExt as _, Stream, channel::oneshot};
 
 use std::sync::{Arc, Weak};
-use std::{future::Future, pin::Pin};
+use std::{future::Future, pin::Pin, time::Duration};
 
-use crate::{Actor, Handler, channel::ChanTx, context::ContextID};
+use crate::{
+    Actor, Handler,
+    channel::ChanTx,
+    context::{ContextID, RunningFuture},
+};
 
-use super::{Addr, Message, Payload, Result, weak_caller::WeakCaller};
+use super::{
+    Addr, CallHandle, Cancellation, Envelope, Message, Payload, Result, weak_caller::WeakCaller,
+};
+
+pub(crate) type CallFuture<R> = Pin<Box<dyn Future<Output = Result<R>> + Send>>;
 
 /// A strong reference to some actor that can receive a message `M` and respond.
 ///
@@ -18,45 +26,112 @@ pub struct Caller<M: Message> {
     call_fn: Box<dyn CallerFn<M>>,
     downgrade_fn: Box<dyn DowngradeFn<M>>,
     id: ContextID,
+    running: RunningFuture,
 }
 
 impl<M: Message> Caller<M> {
     pub async fn call(&self, msg: M) -> Result<M::Response> {
-        self.call_fn.call(msg).await
+        self.call_fn.call(msg, None).await
+    }
+
+    /// See [`Addr::call_cancellable`].
+    pub fn call_cancellable(&self, msg: M) -> CallHandle<M::Response> {
+        let cancellation = Cancellation::default();
+        let response = self.call_fn.call(msg, Some(cancellation.clone()));
+        CallHandle::new(cancellation, response)
+    }
+
+    /// See [`Addr::call_stream`].
+    pub fn call_stream<S>(
+        &self,
+        stream: S,
+        max_in_flight: usize,
+    ) -> impl Stream<Item = Result<M::Response>> + Send + 'static
+    where
+        S: Stream<Item = M> + Send + 'static,
+    {
+        let caller = self.downgrade();
+        let running = self.running.clone();
+        super::call_stream::call_stream(
+            stream,
+            max_in_flight,
+            move |msg| {
+                let caller = caller.clone();
+                async move { caller.try_call(msg).await }
+            },
+            move || running.clone().now_or_never().is_some(),
+        )
+    }
+
+    /// See [`Addr::call_timeout`].
+    pub async fn call_timeout(&self, msg: M, timeout: Duration) -> Result<M::Response> {
+        self.call_cancellable(msg).timeout(timeout).await
     }
 
     pub fn downgrade(&self) -> WeakCaller<M> {
         self.downgrade_fn.downgrade()
     }
 
-    pub(crate) fn new<A>(tx: ChanTx<A>, id: ContextID) -> Self
+    /// Call without a [`CallHandle`], the caller keeps track of the cancellation.
+    #[cfg_attr(not(any(feature = "tokio", feature = "async-std")), allow(dead_code))]
+    pub(crate) fn call_with_cancellation(
+        &self,
+        msg: M,
+        cancellation: Option<Cancellation>,
+    ) -> CallFuture<M::Response> {
+        self.call_fn.call(msg, cancellation)
+    }
+
+    /// A caller that is not backed by a single mailbox, like the one of a [`Pool`](`crate::Pool`).
+    #[cfg_attr(not(any(feature = "tokio", feature = "async-std")), allow(dead_code))]
+    pub(crate) fn from_fns(
+        id: ContextID,
+        running: RunningFuture,
+        call_fn: impl Fn(M, Option<Cancellation>) -> CallFuture<M::Response>
+        + 'static
+        + Send
+        + Sync
+        + Clone,
+        downgrade_fn: impl Fn() -> WeakCaller<M> + 'static + Send + Sync + Clone,
+    ) -> Self {
+        Caller {
+            call_fn: Box::new(call_fn),
+            downgrade_fn: Box::new(downgrade_fn),
+            id,
+            running,
+        }
+    }
+
+    pub(crate) fn new<A>(tx: ChanTx<A>, id: ContextID, running: RunningFuture) -> Self
     where
         A: Actor + Handler<M>,
     {
         let weak_tx: Weak<_> = Arc::downgrade(&tx);
 
         // TODO: make this queue-safe
-        let call_fn = Box::new(
-            move |msg| -> Pin<Box<dyn Future<Output = Result<M::Response>>>> {
-                let tx = Arc::clone(&tx);
-                Box::pin(async move {
-                    let (response_tx, response) = oneshot::channel();
-
-                    // TODO: make this queue-safe
-                    tx.send(Payload::task(move |actor, ctx| {
-                        Box::pin(async move {
-                            let res = Handler::handle(&mut *actor, ctx, msg).await;
-                            let _ = response_tx.send(res);
-                        })
-                    }))
-                    .await?;
-
-                    Ok(response.await?)
-                })
-            },
-        );
+        let call_fn = Box::new(move |msg, cancellation| -> CallFuture<M::Response> {
+            let tx = Arc::clone(&tx);
+            let envelope = Envelope {
+                cancellation,
+                ..Envelope::call::<M>()
+            };
+            let (deadline, panic) = (envelope.deadline, envelope.panic.clone());
+            Box::pin(async move {
+                let (response_tx, response) = oneshot::channel();
+
+                // TODO: make this queue-safe
+                tx.send(Payload::call(envelope, msg, response_tx)).await?;
+
+                super::await_response(response, deadline, panic).await
+            })
+        });
 
-        let upgrade = Box::new(move || weak_tx.upgrade().map(|tx| Caller::new(tx, id)));
+        let running_inner = running.clone();
+        let upgrade = Box::new(move || {
+            weak_tx
+                .upgrade()
+                .map(|tx| Caller::new(tx, id, running_inner.clone()))
+        });
 
         let downgrade_fn = Box::new(move || WeakCaller {
             upgrade: upgrade.clone(),
@@ -67,22 +142,23 @@ impl<M: Message> Caller<M> {
             id,
             call_fn,
             downgrade_fn,
+            running,
         }
     }
 }
 
 trait CallerFn<M: Message>: Send + Sync + 'static + DynClone {
-    fn call(&self, msg: M) -> Pin<Box<dyn Future<Output = Result<M::Response>>>>;
+    fn call(&self, msg: M, cancellation: Option<Cancellation>) -> CallFuture<M::Response>;
 }
 
 impl<F, M> CallerFn<M> for F
 where
-    F: Fn(M) -> Pin<Box<dyn Future<Output = Result<M::Response>>>>,
+    F: Fn(M, Option<Cancellation>) -> CallFuture<M::Response>,
     F: 'static + Send + Sync + Clone,
     M: Message,
 {
-    fn call(&self, msg: M) -> Pin<Box<dyn Future<Output = Result<M::Response>>>> {
-        self(msg)
+    fn call(&self, msg: M, cancellation: Option<Cancellation>) -> CallFuture<M::Response> {
+        self(msg, cancellation)
     }
 }
 
@@ -91,7 +167,11 @@ where
     A: Actor + Handler<M>,
 {
     fn from(addr: Addr<A>) -> Self {
-        Caller::new(addr.payload_tx.to_owned(), addr.context_id)
+        Caller::new(
+            addr.payload_tx.to_owned(),
+            addr.context_id,
+            addr.running.clone(),
+        )
     }
 }
 
@@ -101,6 +181,7 @@ impl<M: Message> Clone for Caller<M> {
             id: self.id,
             call_fn: dyn_clone::clone_box(&*self.call_fn),
             downgrade_fn: dyn_clone::clone_box(&*self.downgrade_fn),
+            running: self.running.clone(),
         }
     }
 }
@@ -0,0 +1,85 @@
+use std::{any::Any, fmt};
+
+use super::Message;
+
+/// Messages that only matter in their latest version.
+///
+/// An actor with a [conflating mailbox](`crate::build`) keeps at most one unprocessed message per key:
+/// a new message replaces a queued one with an equal [`key`](`Conflate::key`) and takes over its place in the queue.
+/// Other mailboxes queue every message.
+///
+/// To opt in, a message also has to return its [`ConflationKey`] from [`Message::conflation_key`],
+/// which `#[message(conflate)]` does for you.
+///
+/// ```rust
+/// # use hannibal::{Conflate, message};
+/// #[message(conflate)]
+/// struct PriceTick {
+///     symbol: &'static str,
+///     price: f64,
+/// }
+///
+/// impl Conflate for PriceTick {
+///     type Key = &'static str;
+///     fn key(&self) -> Self::Key {
+///         self.symbol
+///     }
+/// }
+/// ```
+pub trait Conflate: Message<Response = ()> {
+    /// Messages with equal keys replace each other.
+    type Key: PartialEq + Send + 'static;
+
+    /// The key of this message.
+    fn key(&self) -> Self::Key;
+}
+
+/// Identifies messages that replace each other in a conflating mailbox, see [`Conflate`].
+///
+/// Keys of different message types are never equal.
+pub struct ConflationKey(Box<dyn DynKey>);
+
+impl ConflationKey {
+    /// The key of a [`Conflate`] message.
+    pub fn of<M: Conflate>(msg: &M) -> Self {
+        Self(Box::new(Keyed::<M>(msg.key())))
+    }
+}
+
+impl PartialEq for ConflationKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_key(other.0.as_any())
+    }
+}
+
+impl fmt::Debug for ConflationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ConflationKey")
+            .field(&self.0.message_type())
+            .finish()
+    }
+}
+
+struct Keyed<M: Conflate>(M::Key);
+
+trait DynKey: Send {
+    fn as_any(&self) -> &dyn Any;
+    fn eq_key(&self, other: &dyn Any) -> bool;
+    fn message_type(&self) -> &'static str;
+}
+
+impl<M: Conflate> DynKey for Keyed<M> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_key(&self, other: &dyn Any) -> bool {
+        other
+            .downcast_ref::<Self>()
+            .is_some_and(|other| other.0 == self.0)
+    }
+
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<M>()
+    }
+}
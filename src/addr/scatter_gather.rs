@@ -0,0 +1,253 @@
+//! Sending the same call to several actors and gathering their responses.
+use futures::{
+    StreamExt as _,
+    future::{self, Either},
+    stream::FuturesUnordered,
+};
+
+use std::time::Instant;
+
+use crate::error::{ActorError, Result};
+
+use super::{Message, caller::Caller};
+
+/// Calls a group of actors with clones of the same message.
+///
+/// Every method waits for the responses according to a different policy.
+/// Calls that are no longer needed are cancelled, like a dropped [`CallHandle`](`crate::CallHandle`),
+/// so actors that did not get to the message yet won't handle it.
+///
+/// Built from [`Caller`]s or from anything that converts into one, like an [`Addr`](`crate::Addr`).
+pub struct ScatterGather<M: Message> {
+    callers: Vec<Caller<M>>,
+}
+
+impl<M: Message, C: Into<Caller<M>>> FromIterator<C> for ScatterGather<M> {
+    fn from_iter<I: IntoIterator<Item = C>>(callers: I) -> Self {
+        Self {
+            callers: callers.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<M: Message + Clone> ScatterGather<M> {
+    pub fn new(callers: impl IntoIterator<Item = impl Into<Caller<M>>>) -> Self {
+        callers.into_iter().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.callers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.callers.is_empty()
+    }
+
+    pub fn callers(&self) -> &[Caller<M>] {
+        &self.callers
+    }
+
+    /// Wait for every response, in the order of the callers.
+    pub async fn call_all(&self, msg: M) -> Vec<Result<M::Response>> {
+        future::join_all(self.callers.iter().map(|caller| caller.call(msg.clone()))).await
+    }
+
+    /// The first successful response.
+    ///
+    /// Fails with the last error if every call failed, or with [`ActorError::NoRecipients`] if there are no callers.
+    pub async fn call_first(&self, msg: M) -> Result<M::Response> {
+        let mut pending = self.scatter(&msg);
+        let mut last_error = ActorError::NoRecipients;
+        while let Some((_, response)) = pending.next().await {
+            match response {
+                Ok(response) => return Ok(response),
+                Err(error) => last_error = error,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// The first `quorum` successful responses, in the order they arrived.
+    ///
+    /// Fails with [`ActorError::QuorumNotReached`] as soon as too many calls failed.
+    /// A `quorum` of 0 is reached right away, `Ok(vec![])` is returned without calling anyone.
+    pub async fn call_quorum(&self, msg: M, quorum: usize) -> Result<Vec<M::Response>> {
+        if quorum == 0 {
+            return Ok(Vec::new());
+        }
+        let mut pending = self.scatter(&msg);
+        let mut responses = Vec::with_capacity(quorum);
+        while responses.len() < quorum {
+            if responses.len() + pending.len() < quorum {
+                return Err(ActorError::QuorumNotReached {
+                    quorum,
+                    reached: responses.len(),
+                });
+            }
+            if let Some((_, Ok(response))) = pending.next().await {
+                responses.push(response);
+            }
+        }
+        Ok(responses)
+    }
+
+    /// Every response that arrives before `deadline`, in the order of the callers.
+    ///
+    /// Calls that are still pending at the deadline yield [`ActorError::DeadlineExceeded`].
+    pub async fn call_until(&self, msg: M, deadline: Instant) -> Vec<Result<M::Response>> {
+        let mut responses = self.callers.iter().map(|_| None).collect::<Vec<_>>();
+        let mut pending = self.scatter(&msg);
+        let mut timeout =
+            futures_timer::Delay::new(deadline.saturating_duration_since(Instant::now()));
+        while let Either::Left((Some((index, response)), _)) =
+            future::select(pending.next(), &mut timeout).await
+        {
+            if let Some(slot) = responses.get_mut(index) {
+                *slot = Some(response);
+            }
+        }
+        responses
+            .into_iter()
+            .map(|response| response.unwrap_or(Err(ActorError::DeadlineExceeded)))
+            .collect()
+    }
+
+    /// Start all calls, dropping the returned set cancels the calls that are still pending.
+    fn scatter(
+        &self,
+        msg: &M,
+    ) -> FuturesUnordered<impl Future<Output = (usize, Result<M::Response>)> + use<M>> {
+        self.callers
+            .iter()
+            .enumerate()
+            .map(|(index, caller)| {
+                let call = caller.call_cancellable(msg.clone());
+                async move { (index, call.await) }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::{Duration, Instant};
+
+    use super::ScatterGather;
+    use crate::{Addr, error::ActorError, prelude::*};
+
+    #[derive(Debug)]
+    struct Replica {
+        delay_ms: u64,
+        handled: u32,
+    }
+    impl Actor for Replica {}
+
+    #[derive(Clone)]
+    struct Read;
+    impl Message for Read {
+        type Response = u64;
+    }
+
+    #[derive(Clone)]
+    struct Handled;
+    impl Message for Handled {
+        type Response = u32;
+    }
+
+    impl Handler<Read> for Replica {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, _: Read) -> u64 {
+            tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+            self.handled += 1;
+            self.delay_ms
+        }
+    }
+
+    impl Handler<Handled> for Replica {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, _: Handled) -> u32 {
+            self.handled
+        }
+    }
+
+    /// Replicas answer with their delay, `None` is a replica that already stopped.
+    async fn replicas(delays: &[Option<u64>]) -> Vec<Addr<Replica>> {
+        let mut addrs = Vec::new();
+        for delay in delays {
+            let addr = Replica {
+                delay_ms: delay.unwrap_or_default(),
+                handled: 0,
+            }
+            .spawn();
+            if delay.is_none() {
+                addr.clone().stop_and_join().await.unwrap();
+            }
+            addrs.push(addr);
+        }
+        addrs
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn all_and_first_success() {
+        let addrs = replicas(&[Some(30), None, Some(10)]).await;
+        let group = addrs.iter().cloned().collect::<ScatterGather<Read>>();
+
+        assert_eq!(
+            group.call_all(Read).await,
+            vec![Ok(30), Err(ActorError::AlreadyStopped), Ok(10)]
+        );
+        assert_eq!(group.call_first(Read).await, Ok(10));
+        assert_eq!(
+            ScatterGather::<Read>::new(Vec::<Addr<Replica>>::new())
+                .call_first(Read)
+                .await,
+            Err(ActorError::NoRecipients)
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn quorum() {
+        let addrs = replicas(&[Some(40), Some(1), Some(5), None]).await;
+        let group = ScatterGather::<Read>::new(addrs.iter().cloned());
+
+        assert_eq!(group.call_quorum(Read, 0).await, Ok(vec![]));
+        assert_eq!(group.call_quorum(Read, 2).await, Ok(vec![1, 5]));
+        assert_eq!(
+            group.call_quorum(Read, 4).await,
+            Err(ActorError::QuorumNotReached {
+                quorum: 4,
+                reached: 0
+            })
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn until_deadline_cancels_stragglers() {
+        let addrs = replicas(&[Some(200), Some(200), Some(1)]).await;
+        let busy_addr = addrs.get(1).unwrap().clone();
+        let callers = addrs.iter().map(Addr::caller).collect::<Vec<_>>();
+        let group = ScatterGather::new(callers);
+
+        // the second replica is still busy with another read when the deadline passes
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let busy = tokio::spawn({
+            let addr = busy_addr.clone();
+            async move { addr.call(Read).await }
+        });
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(
+            group.call_until(Read, deadline).await,
+            vec![
+                Err(ActorError::DeadlineExceeded),
+                Err(ActorError::DeadlineExceeded),
+                Ok(1)
+            ]
+        );
+        busy.await.unwrap().unwrap();
+        assert_eq!(
+            busy_addr.call(Handled).await,
+            Ok(1),
+            "cancelled read is never handled"
+        );
+    }
+}
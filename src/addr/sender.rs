@@ -12,7 +12,11 @@ use crate::{
     context::ContextID,
 };
 
-use super::{Addr, Message, Payload, Result, weak_sender::WeakSender};
+use super::{
+    Addr, Envelope, Message, Payload, Result,
+    sink::{ActorSink, PendingSend},
+    weak_sender::WeakSender,
+};
 
 /// A strong reference to some actor that can receive message `M`.
 ///
@@ -20,11 +24,14 @@ use super::{Addr, Message, Payload, Result, weak_sender::WeakSender};
 /// If you need a response, use [`Caller`](`crate::Caller`) instead.
 ///
 /// Senders can be downgraded to [`WeakSender`](`crate::WeakSender`) to check if the actor is still alive.
+///
+/// Senders are [`Sink`](`futures::Sink`)s, so you can forward a stream to an actor.
 pub struct Sender<M: Message<Response = ()>> {
     send_fn: Box<dyn SenderFn<M>>,
     force_send_fn: Box<dyn ForceSenderFn<M>>,
     downgrade_fn: Box<dyn DowngradeFn<M>>,
     id: ContextID,
+    pub(super) pending: PendingSend,
 }
 
 impl<M: Message<Response = ()>> Sender<M> {
@@ -40,6 +47,32 @@ impl<M: Message<Response = ()>> Sender<M> {
         self.downgrade_fn.downgrade()
     }
 
+    /// A [`Sink`](`futures::Sink`) that sends to the same actor.
+    pub fn sink(&self) -> ActorSink<M> {
+        ActorSink::new(self.clone())
+    }
+
+    /// A sender that is not backed by a single mailbox, like the one of a [`Pool`](`crate::Pool`).
+    #[cfg_attr(not(any(feature = "tokio", feature = "async-std")), allow(dead_code))]
+    pub(crate) fn from_fns(
+        id: ContextID,
+        send_fn: impl Fn(M) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>
+        + 'static
+        + Send
+        + Sync
+        + Clone,
+        force_send_fn: impl Fn(M) -> Result<()> + 'static + Send + Sync + Clone,
+        downgrade_fn: impl Fn() -> WeakSender<M> + 'static + Send + Sync + Clone,
+    ) -> Self {
+        Sender {
+            id,
+            send_fn: Box::new(send_fn),
+            force_send_fn: Box::new(force_send_fn),
+            downgrade_fn: Box::new(downgrade_fn),
+            pending: PendingSend::default(),
+        }
+    }
+
     pub(crate) fn new<A>(tx: ChanTx<A>, force_tx: ForceChanTx<A>, id: ContextID) -> Self
     where
         A: Actor + Handler<M>,
@@ -47,17 +80,10 @@ impl<M: Message<Response = ()>> Sender<M> {
         let weak_tx: Weak<_> = Arc::downgrade(&tx);
         let weak_force_tx: Weak<_> = Arc::downgrade(&force_tx);
 
-        let send_fn = Box::new(move |msg| {
-            tx.send(Payload::task(move |actor, ctx| {
-                Box::pin(Handler::handle(&mut *actor, ctx, msg))
-            }))
-        });
+        let send_fn = Box::new(move |msg| tx.send(Payload::handle(Envelope::of(&msg), msg)));
 
-        let force_send_fn = Box::new(move |msg| {
-            force_tx.send(Payload::task(move |actor, ctx| {
-                Box::pin(Handler::handle(&mut *actor, ctx, msg))
-            }))
-        });
+        let force_send_fn =
+            Box::new(move |msg| force_tx.send(Payload::handle(Envelope::of(&msg), msg)));
 
         let upgrade = Box::new(move || {
             weak_tx
@@ -69,6 +95,8 @@ impl<M: Message<Response = ()>> Sender<M> {
         let downgrade_fn = Box::new(move || WeakSender {
             upgrade: upgrade.clone(),
             id,
+            actor: A::NAME,
+            pending: PendingSend::default(),
         });
 
         Sender {
@@ -76,6 +104,7 @@ impl<M: Message<Response = ()>> Sender<M> {
             send_fn,
             force_send_fn,
             downgrade_fn,
+            pending: PendingSend::default(),
         }
     }
 }
@@ -141,6 +170,7 @@ impl<M: Message<Response = ()>> Clone for Sender<M> {
             send_fn: dyn_clone::clone_box(&*self.send_fn),
             force_send_fn: dyn_clone::clone_box(&*self.force_send_fn),
             downgrade_fn: dyn_clone::clone_box(&*self.downgrade_fn),
+            pending: PendingSend::default(),
         }
     }
 }
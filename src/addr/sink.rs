@@ -0,0 +1,230 @@
+//! A [`Sink`], so actors can be fed from streams.
+use futures::Sink;
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Mutex, PoisonError},
+    task::{Context, Poll, ready},
+};
+
+use crate::error::{ActorError::AlreadyStopped, Result};
+
+use super::{Message, sender::Sender, weak_sender::WeakSender};
+
+type SendFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// The message a [`Sender`] or [`WeakSender`] is currently handing to the mailbox as a [`Sink`].
+///
+/// Kept behind a [`Mutex`] so that senders stay `Sync`, it is only ever accessed through `&mut`.
+#[derive(Default)]
+pub(crate) struct PendingSend(Mutex<Option<SendFuture>>);
+
+impl PendingSend {
+    fn slot(&mut self) -> &mut Option<SendFuture> {
+        self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn start(&mut self, send: SendFuture) {
+        *self.slot() = Some(send);
+    }
+
+    /// Wait until the pending message is queued, a bounded mailbox may have to make room first.
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let slot = self.slot();
+        if let Some(send) = slot {
+            let result = ready!(send.as_mut().poll(cx));
+            *slot = None;
+            result?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<M: Message<Response = ()>> Sink<M> for Sender<M> {
+    type Error = crate::error::ActorError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.pending.poll(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, msg: M) -> Result<()> {
+        let send = self.send(msg);
+        self.pending.start(send);
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.pending.poll(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.pending.poll(cx)
+    }
+}
+
+impl<M: Message<Response = ()>> Sink<M> for WeakSender<M> {
+    type Error = crate::error::ActorError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.pending.poll(cx))?;
+        if self.upgrade().is_none() {
+            return Poll::Ready(Err(AlreadyStopped));
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, msg: M) -> Result<()> {
+        let Some(strong) = self.upgrade() else {
+            self.report_stopped();
+            return Err(AlreadyStopped);
+        };
+        self.pending.start(strong.send(msg));
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.pending.poll(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.pending.poll(cx)
+    }
+}
+
+enum Target<M: Message<Response = ()>> {
+    Strong(Sender<M>),
+    Weak(WeakSender<M>),
+}
+
+/// A [`Sink`] for one message type, returned by [`Addr::sink`](`crate::Addr::sink`).
+///
+/// [`Sender`] and [`WeakSender`] implement [`Sink`] themselves, this is what they look like
+/// once picked from an [`Addr`](`crate::Addr`).
+/// [`poll_ready`](`Sink::poll_ready`) waits while a bounded mailbox is full.
+/// A sink made from a [`WeakSender`] fails with [`AlreadyStopped`] once the actor is gone.
+pub struct ActorSink<M: Message<Response = ()>> {
+    target: Target<M>,
+}
+
+impl<M: Message<Response = ()>> ActorSink<M> {
+    pub(crate) const fn new(sender: Sender<M>) -> Self {
+        Self {
+            target: Target::Strong(sender),
+        }
+    }
+
+    pub(crate) const fn weak(sender: WeakSender<M>) -> Self {
+        Self {
+            target: Target::Weak(sender),
+        }
+    }
+}
+
+impl<M: Message<Response = ()>> Sink<M> for ActorSink<M> {
+    type Error = crate::error::ActorError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        match &mut self.target {
+            Target::Strong(sender) => Pin::new(sender).poll_ready(cx),
+            Target::Weak(sender) => Pin::new(sender).poll_ready(cx),
+        }
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, msg: M) -> Result<()> {
+        match &mut self.target {
+            Target::Strong(sender) => Pin::new(sender).start_send(msg),
+            Target::Weak(sender) => Pin::new(sender).start_send(msg),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        match &mut self.target {
+            Target::Strong(sender) => Pin::new(sender).poll_flush(cx),
+            Target::Weak(sender) => Pin::new(sender).poll_flush(cx),
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        match &mut self.target {
+            Target::Strong(sender) => Pin::new(sender).poll_close(cx),
+            Target::Weak(sender) => Pin::new(sender).poll_close(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use futures::{SinkExt as _, StreamExt as _, stream};
+
+    use crate::{Sender, WeakSender, error::ActorError, prelude::*};
+
+    #[derive(Debug, Default)]
+    struct Collect(Vec<u32>);
+    impl Actor for Collect {}
+
+    struct Item(u32);
+    impl Message for Item {
+        type Response = ();
+    }
+
+    impl Handler<Item> for Collect {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, Item(n): Item) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            self.0.push(n);
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn forwards_stream_into_bounded_mailbox() {
+        let addr = crate::build(Collect::default()).bounded(2).spawn_owning();
+
+        let mailbox = addr.to_addr();
+        let items = stream::iter(0..20).map(Item).inspect(move |_| {
+            assert!(mailbox.mailbox_len() <= 2, "sink must wait for room");
+        });
+        items.map(Ok).forward(addr.to_addr().sink()).await.unwrap();
+
+        assert_eq!(addr.consume().await.unwrap().0, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn weak_sink_fails_once_actor_is_gone() {
+        let addr = Collect::default().spawn();
+        let weak: WeakSender<Item> = addr.weak_sender();
+        let mut sink = weak.sink();
+
+        sink.send(Item(1)).await.unwrap();
+        addr.stop_and_join().await.unwrap();
+        assert_eq!(sink.send(Item(2)).await, Err(ActorError::AlreadyStopped));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn sender_is_a_sink() {
+        let addr = crate::build(Collect::default()).bounded(2).spawn_owning();
+
+        let sender: Sender<Item> = addr.to_addr().sender();
+        stream::iter(0..5)
+            .map(Item)
+            .map(Ok)
+            .forward(sender)
+            .await
+            .unwrap();
+
+        assert_eq!(addr.consume().await.unwrap().0, (0..5).collect::<Vec<_>>());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn weak_sender_is_a_sink() {
+        let addr = Collect::default().spawn();
+        let mut weak: WeakSender<Item> = addr.weak_sender();
+
+        weak.send(Item(1)).await.unwrap();
+        addr.stop_and_join().await.unwrap();
+        assert_eq!(weak.send(Item(2)).await, Err(ActorError::AlreadyStopped));
+    }
+}
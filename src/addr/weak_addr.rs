@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use dyn_clone::DynClone;
 
+use crate::channel::{MailboxStats, MailboxStatsFn};
 use crate::context::{ContextID, RunningFuture};
 use crate::{Actor, Addr};
 
@@ -11,6 +12,7 @@ use crate::{Actor, Addr};
 pub struct WeakAddr<A: Actor> {
     pub(crate) context_id: ContextID,
     pub(super) upgrade: Box<dyn UpgradeFn<A>>,
+    pub(crate) mailbox: MailboxStatsFn,
     pub(crate) running: RunningFuture,
 }
 
@@ -22,6 +24,16 @@ impl<A: Actor> WeakAddr<A> {
     pub fn stopped(&self) -> bool {
         self.running.peek().is_some()
     }
+
+    /// The id of the actor's [`Context`](`crate::Context`).
+    pub const fn id(&self) -> ContextID {
+        self.context_id
+    }
+
+    /// A snapshot of the actor's mailbox, see [`Addr::mailbox_stats`].
+    pub fn mailbox_stats(&self) -> MailboxStats {
+        (self.mailbox)()
+    }
 }
 
 impl<A: Actor> From<&Addr<A>> for WeakAddr<A> {
@@ -31,8 +43,11 @@ impl<A: Actor> From<&Addr<A>> for WeakAddr<A> {
         let context_id = addr.context_id;
         let running = addr.running.clone();
         let running_inner = addr.running.clone();
+        let mailbox = Arc::clone(&addr.mailbox);
+        let mailbox_inner = Arc::clone(&addr.mailbox);
         let upgrade = Box::new(move || {
             let running = running_inner.clone();
+            let mailbox = Arc::clone(&mailbox_inner);
             weak_tx
                 .upgrade()
                 .zip(weak_force_tx.upgrade())
@@ -40,6 +55,7 @@ impl<A: Actor> From<&Addr<A>> for WeakAddr<A> {
                     context_id,
                     payload_force_tx,
                     payload_tx,
+                    mailbox,
                     running,
                 })
         });
@@ -47,6 +63,7 @@ impl<A: Actor> From<&Addr<A>> for WeakAddr<A> {
         WeakAddr {
             context_id,
             upgrade,
+            mailbox,
             running,
         }
     }
@@ -57,6 +74,7 @@ impl<A: Actor> Clone for WeakAddr<A> {
         WeakAddr {
             context_id: self.context_id,
             upgrade: dyn_clone::clone_box(&*self.upgrade),
+            mailbox: Arc::clone(&self.mailbox),
             running: self.running.clone(),
         }
     }
@@ -1,11 +1,11 @@
 use dyn_clone::DynClone;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
     Actor, Handler,
     channel::{ChanTx, WeakChanTx},
-    context::ContextID,
+    context::{ContextID, RunningFuture},
     error::ActorError::AlreadyStopped,
 };
 
@@ -33,20 +33,49 @@ impl<M: Message> WeakCaller<M> {
         }
     }
 
-    fn new<A>(tx: ChanTx<A>, id: ContextID) -> Self
+    /// See [`Addr::call_timeout`].
+    pub async fn try_call_timeout(&self, msg: M, timeout: Duration) -> Result<M::Response> {
+        if let Some(caller) = self.upgrade.upgrade() {
+            caller.call_timeout(msg, timeout).await
+        } else {
+            Err(AlreadyStopped)
+        }
+    }
+
+    /// A weak caller that is not backed by a single mailbox, like the one of a [`Pool`](`crate::Pool`).
+    #[cfg_attr(not(any(feature = "tokio", feature = "async-std")), allow(dead_code))]
+    pub(crate) fn from_upgrade(
+        id: ContextID,
+        upgrade: impl Fn() -> Option<Caller<M>> + 'static + Send + Sync + Clone,
+    ) -> Self {
+        WeakCaller {
+            upgrade: Box::new(upgrade),
+            id,
+        }
+    }
+
+    fn new<A>(tx: ChanTx<A>, id: ContextID, running: RunningFuture) -> Self
     where
         A: Actor + Handler<M>,
         M: Message,
     {
-        Self::from_weak_tx(Arc::downgrade(&tx), id)
+        Self::from_weak_tx(Arc::downgrade(&tx), id, running)
     }
 
-    pub(crate) fn from_weak_tx<A>(weak_tx: WeakChanTx<A>, id: ContextID) -> Self
+    pub(crate) fn from_weak_tx<A>(
+        weak_tx: WeakChanTx<A>,
+        id: ContextID,
+        running: RunningFuture,
+    ) -> Self
     where
         A: Actor + Handler<M>,
         M: Message,
     {
-        let upgrade = Box::new(move || weak_tx.upgrade().map(|tx| Caller::new(tx, id)));
+        let upgrade = Box::new(move || {
+            weak_tx
+                .upgrade()
+                .map(|tx| Caller::new(tx, id, running.clone()))
+        });
 
         WeakCaller { upgrade, id }
     }
@@ -57,7 +86,11 @@ where
     A: Actor + Handler<M>,
 {
     fn from(addr: Addr<A>) -> Self {
-        Self::new(addr.payload_tx.to_owned(), addr.context_id)
+        Self::new(
+            addr.payload_tx.to_owned(),
+            addr.context_id,
+            addr.running.clone(),
+        )
     }
 }
 
@@ -66,7 +99,11 @@ where
     A: Actor + Handler<M>,
 {
     fn from(addr: &Addr<A>) -> Self {
-        Self::new(addr.payload_tx.to_owned(), addr.context_id)
+        Self::new(
+            addr.payload_tx.to_owned(),
+            addr.context_id,
+            addr.running.clone(),
+        )
     }
 }
 
@@ -6,18 +6,27 @@ use crate::{
     Actor, Handler,
     channel::{ChanTx, ForceChanTx, WeakChanTx, WeakForceChanTx},
     context::ContextID,
+    dead_letters::{self, DeadLetter, DeadLetterReason},
     error::ActorError::AlreadyStopped,
 };
 
-use super::{Addr, Message, Result, sender::Sender};
+use super::{
+    Addr, Message, Result,
+    sender::Sender,
+    sink::{ActorSink, PendingSend},
+};
 
 /// A weak reference to an actor that can receive a message `M`.
 ///
 /// This is the weak counterpart to [`Sender`].
 /// It can be upgraded if the Actor is still alive.
+/// As a [`Sink`](`futures::Sink`) it fails with [`AlreadyStopped`] once the actor is gone.
 pub struct WeakSender<M> {
     pub(super) upgrade: Box<dyn UpgradeFn<M>>,
     pub(crate) id: ContextID,
+    /// The [`NAME`](`Actor::NAME`) of the actor, for reporting dead letters.
+    pub(crate) actor: &'static str,
+    pub(super) pending: PendingSend,
 }
 
 impl<M: Message<Response = ()>> WeakSender<M> {
@@ -29,6 +38,7 @@ impl<M: Message<Response = ()>> WeakSender<M> {
         if let Some(sender) = self.upgrade.upgrade() {
             sender.force_send(msg)
         } else {
+            self.report_stopped();
             Err(AlreadyStopped)
         }
     }
@@ -37,10 +47,40 @@ impl<M: Message<Response = ()>> WeakSender<M> {
         if let Some(sender) = self.upgrade.upgrade() {
             sender.send(msg).await
         } else {
+            self.report_stopped();
             Err(AlreadyStopped)
         }
     }
 
+    /// A [`Sink`](`futures::Sink`) that fails with [`AlreadyStopped`] once the actor is gone.
+    pub fn sink(&self) -> ActorSink<M> {
+        ActorSink::weak(self.clone())
+    }
+
+    /// A weak sender that is not backed by a single mailbox, like the one of a [`Pool`](`crate::Pool`).
+    #[cfg_attr(not(any(feature = "tokio", feature = "async-std")), allow(dead_code))]
+    pub(crate) fn from_upgrade(
+        id: ContextID,
+        actor: &'static str,
+        upgrade: impl Fn() -> Option<Sender<M>> + 'static + Send + Sync + Clone,
+    ) -> Self {
+        WeakSender {
+            upgrade: Box::new(upgrade),
+            id,
+            actor,
+            pending: PendingSend::default(),
+        }
+    }
+
+    pub(super) fn report_stopped(&self) {
+        dead_letters::report(DeadLetter {
+            target: self.id,
+            actor: self.actor,
+            message: std::any::type_name::<M>(),
+            reason: DeadLetterReason::ActorStopped,
+        });
+    }
+
     fn new<A>(tx: ChanTx<A>, force_tx: ForceChanTx<A>, id: ContextID) -> Self
     where
         A: Actor + Handler<M>,
@@ -65,7 +105,12 @@ impl<M: Message<Response = ()>> WeakSender<M> {
                 .map(|(tx, force_tx)| Sender::new(tx, force_tx, id))
         });
 
-        WeakSender { upgrade, id }
+        WeakSender {
+            upgrade,
+            id,
+            actor: A::NAME,
+            pending: PendingSend::default(),
+        }
     }
 }
 
@@ -100,6 +145,8 @@ impl<M: Message<Response = ()>> Clone for WeakSender<M> {
         WeakSender {
             upgrade: dyn_clone::clone_box(&*self.upgrade),
             id: self.id,
+            actor: self.actor,
+            pending: PendingSend::default(),
         }
     }
 }
@@ -5,6 +5,8 @@ use crate::{Actor, Addr, Context, Handler, Message, Service, WeakSender, context
 /// Enables global subscriptions and message distribution.
 ///
 /// The `Broker` is a service actor that allows actors to publish and subscribe to messages by type.
+/// Besides the global broker in the registry, separate brokers can be [created](`Broker::new`) and spawned,
+/// actors subscribe to those with [`Context::subscribe_to`].
 ///
 /// # Example
 /// ```
@@ -49,6 +51,25 @@ pub struct Broker<T: Message<Response = ()>> {
     subscribers: HashMap<ContextID, WeakSender<T>>,
 }
 
+impl<T: Message<Response = ()>> Broker<T> {
+    /// A broker that is independent of the global one, spawn it and hand out its address.
+    ///
+    /// ```
+    /// # use hannibal::{Broker, prelude::*};
+    /// # #[derive(Clone, Message)]
+    /// # struct Event;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let tenant_a = Broker::<Event>::new().spawn();
+    /// let tenant_b = Broker::<Event>::new().spawn();
+    /// tenant_a.publish(Event).await.unwrap(); // not seen by subscribers of `tenant_b`
+    /// # }
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 impl<T: Message<Response = ()> + Clone> Broker<T> {
     /// Publishes a message to all subscribers.
     pub async fn publish(topic: T) -> crate::error::Result<()> {
@@ -95,8 +116,9 @@ impl<T: Message<Response = ()> + Clone> Handler<Publish<T>> for Broker<T> {
             .filter_map(WeakSender::upgrade)
             .collect::<Vec<_>>();
         for subscriber in &live_subscribers {
-            if let Err(_error) = subscriber.send(msg.0.clone()).await {
-                // log::warn!("Failed to send message to subscriber: {:?}", error)
+            // the subscriber's mailbox reports the message as a dead letter
+            if let Err(error) = subscriber.send(msg.0.clone()).await {
+                log::warn!("Failed to send message to subscriber: {error}");
             }
         }
         log::trace!(
@@ -162,7 +184,8 @@ mod subscribe_publish_unsubscribe {
     use futures::future::join;
 
     use crate::{
-        Actor, Broker, Context, DynResult, Handler, Message, Service, prelude::Spawnable as _,
+        Actor, Broker, Context, DynResult, Handler, Message, Service, StopReason,
+        prelude::Spawnable as _,
     };
 
     #[derive(Clone, Debug)]
@@ -206,9 +229,70 @@ mod subscribe_publish_unsubscribe {
 
         let _ = ping_both().await;
 
-        assert_eq!(subscriber1.join().await, Some(Subscribing(vec![42, 23])));
-        assert_eq!(subscriber2.join().await, Some(Subscribing(vec![42, 23])));
+        assert_eq!(
+            subscriber1.join().await,
+            (Some(Subscribing(vec![42, 23])), StopReason::Stopped)
+        );
+        assert_eq!(
+            subscriber2.join().await,
+            (Some(Subscribing(vec![42, 23])), StopReason::Stopped)
+        );
 
         Ok(())
     }
+
+    /// Only used here, publishing to the global broker does not disturb the other test.
+    #[derive(Clone, Debug)]
+    struct Topic2(u32);
+    impl Message for Topic2 {
+        type Response = ();
+    }
+
+    #[derive(Default, Debug, PartialEq)]
+    struct Scoped(Vec<u32>);
+
+    impl Actor for Scoped {}
+
+    impl Handler<Topic2> for Scoped {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, msg: Topic2) {
+            self.0.push(msg.0);
+        }
+    }
+
+    struct SubscribeTo(crate::Addr<Broker<Topic2>>);
+    impl Message for SubscribeTo {
+        type Response = ();
+    }
+
+    impl Handler<SubscribeTo> for Scoped {
+        async fn handle(&mut self, ctx: &mut Context<Self>, SubscribeTo(broker): SubscribeTo) {
+            ctx.subscribe_to(&broker).await.unwrap();
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn scoped_brokers_are_isolated() {
+        let broker_a = Broker::<Topic2>::new().spawn();
+        let broker_b = Broker::<Topic2>::new().spawn();
+        let subscriber_a = Scoped::default().spawn_owning();
+        let subscriber_b = Scoped::default().spawn_owning();
+        subscriber_a
+            .call(SubscribeTo(broker_a.clone()))
+            .await
+            .unwrap();
+        subscriber_b
+            .call(SubscribeTo(broker_b.clone()))
+            .await
+            .unwrap();
+
+        broker_a.publish(Topic2(1)).await.unwrap();
+        broker_b.publish(Topic2(2)).await.unwrap();
+        broker_a.publish(Topic2(3)).await.unwrap();
+        Broker::publish(Topic2(4)).await.unwrap();
+        broker_a.ping().await.unwrap();
+        broker_b.ping().await.unwrap();
+
+        assert_eq!(subscriber_a.consume().await, Ok(Scoped(vec![1, 3])));
+        assert_eq!(subscriber_b.consume().await, Ok(Scoped(vec![2])));
+    }
 }
@@ -1,16 +1,15 @@
-use futures::{
-    Stream as _,
-    stream::{PollFn, poll_fn},
-    task,
-};
+use futures::{stream::PollFn, task};
 
 use std::{
     future::Future,
-    pin::{Pin, pin},
+    pin::Pin,
     sync::{Arc, Weak},
 };
 
-use crate::{environment::Payload, error::Result};
+use crate::{context::ContextID, environment::Payload, error::Result};
+
+mod mailbox;
+pub use mailbox::{MailboxStats, OverflowPolicy};
 
 pub type WeakChanTx<A> = Weak<dyn TxFn<A>>;
 pub type ChanTx<A> = Arc<dyn TxFn<A>>;
@@ -32,6 +31,12 @@ where
     }
 }
 
+/// Takes a snapshot of the mailbox without keeping the actor alive.
+pub type MailboxStatsFn = Arc<dyn Fn() -> MailboxStats + Send + Sync>;
+
+/// Tells the mailbox which actor owns it, so that it can report [`DeadLetter`](`crate::DeadLetter`)s.
+pub type BindFn = Arc<dyn Fn(ContextID, &'static str) + Send + Sync>;
+
 pub type ForceChanTx<A> = Arc<dyn ForceTxFn<A>>;
 pub type WeakForceChanTx<A> = Weak<dyn ForceTxFn<A>>;
 
@@ -49,19 +54,53 @@ where
     }
 }
 
+/// How an actor's mailbox is set up, the channel is only created when the actor is spawned.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ChannelConfig {
+    pub capacity: Option<usize>,
+    pub overflow: OverflowPolicy,
+    pub priority: bool,
+    pub conflate: bool,
+}
+
+impl ChannelConfig {
+    /// An unbounded FIFO mailbox.
+    pub const DEFAULT: Self = Self {
+        capacity: None,
+        overflow: OverflowPolicy::Block,
+        priority: false,
+        conflate: false,
+    };
+
+    #[cfg_attr(not(any(feature = "tokio", feature = "async-std")), allow(dead_code))]
+    pub fn build<A: 'static>(self) -> Channel<A> {
+        Channel::mailbox(self)
+    }
+}
+
 // TODO: consider getting rid of this and just using `Sink` and `
 pub(crate) struct Channel<A> {
     tx_fn: ChanTx<A>,
     force_tx_fn: ForceChanTx<A>,
     stream: PayloadStream<A>,
+    stats: MailboxStatsFn,
+    bind: BindFn,
 }
 
 impl<A> Channel<A> {
-    fn new(tx_fn: ChanTx<A>, force_tx_fn: ForceChanTx<A>, stream: PayloadStream<A>) -> Self {
+    fn new(
+        tx_fn: ChanTx<A>,
+        force_tx_fn: ForceChanTx<A>,
+        stream: PayloadStream<A>,
+        stats: MailboxStatsFn,
+        bind: BindFn,
+    ) -> Self {
         Channel {
             tx_fn,
             force_tx_fn,
             stream,
+            stats,
+            bind,
         }
     }
 }
@@ -70,72 +109,32 @@ impl<A> Channel<A>
 where
     for<'a> A: 'a,
 {
-    pub fn bounded(buffer: usize) -> Self {
-        let (tx, mut rx) = futures::channel::mpsc::channel::<Payload<A>>(buffer);
-        let tx2 = tx.clone();
-
-        let send = Arc::new(
-            move |event: Payload<A>| -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
-                let tx = tx2.clone();
-                Box::pin(async move {
-                    let mut tx = tx.clone();
-                    futures::SinkExt::send(&mut tx, event).await?;
-                    Ok(())
-                })
-            },
-        );
-
-        let force_send = Arc::new(move |event: Payload<A>| -> Result<()> {
-            let mut tx = tx.clone();
-            // THIS IS A BUG!
-            // Just calling this without checking for readyness will just queue this and ignore the bound
-            tx.start_send(event)?;
-            Ok(())
-        });
-
-        let recv: PayloadStream<A> = poll_fn(Box::new(move |ctx| {
-            let pinned = pin!(&mut rx);
-            pinned.poll_next(ctx)
-        }));
-
-        Self::new(send, force_send, recv)
+    /// A mailbox that holds up to `capacity` messages, senders wait while it is full.
+    pub fn bounded(capacity: usize) -> Self {
+        Self::mailbox(ChannelConfig {
+            capacity: Some(capacity),
+            ..ChannelConfig::DEFAULT
+        })
     }
 
+    /// A mailbox without a capacity.
     pub fn unbounded() -> Self {
-        let (tx, mut rx) = futures::channel::mpsc::unbounded::<Payload<A>>();
-        let tx2 = tx.clone();
-
-        let send = Arc::new(
-            move |event: Payload<A>| -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
-                let tx = tx2.clone();
-                Box::pin(async move {
-                    let mut tx = tx.clone();
-                    // THIS IS A BUG!
-                    // Just calling this without checking for readyness will just queue this and ignore the bound
-                    futures::SinkExt::send(&mut tx, event).await?;
-                    Ok(())
-                })
-            },
-        );
-
-        let force_send = Arc::new(move |event: Payload<A>| -> Result<()> {
-            log::trace!("sending (unbounded {})", tx.len());
-            let mut tx = tx.clone();
-            tx.start_send(event)?;
-            Ok(())
-        });
-        let recv: PayloadStream<A> = poll_fn(Box::new(move |ctx| {
-            let pinned = pin!(&mut rx);
-            pinned.poll_next(ctx)
-        }));
-
-        Self::new(send, force_send, recv)
+        Self::mailbox(ChannelConfig::DEFAULT)
     }
 
     pub fn break_up(self) -> (ForceChanTx<A>, ChanTx<A>, PayloadStream<A>) {
         (self.force_tx_fn, self.tx_fn, self.stream)
     }
 
+    pub fn stats(&self) -> MailboxStatsFn {
+        Arc::clone(&self.stats)
+    }
+
+    /// Let the mailbox know which actor it belongs to.
+    pub fn bind(&self, id: ContextID, actor: &'static str) {
+        (self.bind)(id, actor);
+    }
+
     pub fn weak_force_tx(&self) -> WeakForceChanTx<A> {
         Arc::downgrade(&self.force_tx_fn)
     }
@@ -143,3 +142,330 @@ where
         Arc::downgrade(&self.tx_fn)
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use crate::{
+        Conflate, ConflationKey, MailboxStats, OverflowPolicy, Priority, error::ActorError,
+        prelude::*,
+    };
+
+    #[derive(Debug, Default)]
+    struct Recorder(Vec<&'static str>);
+    impl Actor for Recorder {}
+
+    struct Block(Duration);
+    impl Message for Block {
+        type Response = ();
+    }
+
+    struct Record(&'static str);
+    impl Message for Record {
+        type Response = ();
+    }
+
+    struct Urgent;
+    impl Message for Urgent {
+        type Response = ();
+        const PRIORITY: Priority = Priority::High;
+    }
+
+    struct Progress(&'static str, &'static str);
+    impl Message for Progress {
+        type Response = ();
+        fn conflation_key(&self) -> Option<ConflationKey> {
+            Some(ConflationKey::of(self))
+        }
+    }
+    impl Conflate for Progress {
+        type Key = &'static str;
+        fn key(&self) -> Self::Key {
+            self.0
+        }
+    }
+
+    impl Handler<Progress> for Recorder {
+        async fn handle(&mut self, _: &mut Context<Self>, Progress(_, value): Progress) {
+            self.0.push(value);
+        }
+    }
+
+    impl Handler<Block> for Recorder {
+        async fn handle(&mut self, _: &mut Context<Self>, Block(duration): Block) {
+            tokio::time::sleep(duration).await;
+        }
+    }
+
+    impl Handler<Record> for Recorder {
+        async fn handle(&mut self, _: &mut Context<Self>, Record(name): Record) {
+            self.0.push(name);
+        }
+    }
+
+    impl Handler<Urgent> for Recorder {
+        async fn handle(&mut self, _: &mut Context<Self>, _: Urgent) {
+            self.0.push("urgent");
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn drains_higher_priorities_first() {
+        let addr = crate::build(Recorder::default())
+            .bounded_priority(10)
+            .spawn_owning();
+
+        addr.send(Block(Duration::from_millis(50))).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        addr.send_with_priority(Record("low"), Priority::Low)
+            .await
+            .unwrap();
+        addr.send(Record("normal")).await.unwrap();
+        addr.send(Urgent).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (recorder, reason) = addr.stop_and_join().unwrap().await;
+        assert_eq!(reason, StopReason::Stopped);
+        assert_eq!(recorder.unwrap().0, vec!["urgent", "normal", "low"]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn stop_is_not_starved_by_traffic() {
+        let addr = crate::build(Recorder::default())
+            .bounded_priority(4)
+            .spawn_owning();
+
+        let flood = tokio::spawn({
+            let addr = addr.to_addr();
+            async move { while addr.send(Block(Duration::from_millis(1))).await.is_ok() {} }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // messages sent after the stop must not keep the actor alive
+        let (recorder, reason) =
+            tokio::time::timeout(Duration::from_secs(1), addr.stop_and_join().unwrap())
+                .await
+                .unwrap();
+        assert_eq!(reason, StopReason::Stopped);
+        assert!(recorder.is_some());
+        flood.await.unwrap();
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn stop_goes_ahead_of_queued_messages() {
+        let addr = crate::build(Recorder::default())
+            .bounded_priority(10)
+            .spawn_owning();
+
+        addr.send(Block(Duration::from_millis(50))).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let sender = addr.to_addr();
+        sender.send(Record("normal")).await.unwrap();
+        let stopped = addr.stop_and_join().unwrap();
+        sender.send(Urgent).await.unwrap();
+
+        let (recorder, reason) = stopped.await;
+        assert_eq!(reason, StopReason::Stopped);
+        assert!(recorder.unwrap().0.is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn priorities_share_the_capacity() {
+        let addr = crate::build(Recorder::default())
+            .bounded_priority(2)
+            .overflow(OverflowPolicy::Reject)
+            .spawn_owning();
+        assert_eq!(addr.mailbox_stats().capacity, Some(2));
+
+        addr.send(Block(Duration::from_millis(50))).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let mut results = Vec::new();
+        for priority in [Priority::Low, Priority::Normal, Priority::High] {
+            results.push(addr.send_with_priority(Record("x"), priority).await);
+        }
+        assert_eq!(results, vec![Ok(()), Ok(()), Err(ActorError::MailboxFull)]);
+        assert_eq!(addr.mailbox_len(), 2);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn fifo_mailbox_ignores_priority() {
+        let addr = crate::build(Recorder::default()).unbounded().spawn_owning();
+
+        addr.send(Block(Duration::from_millis(50))).await.unwrap();
+        addr.send_with_priority(Record("low"), Priority::Low)
+            .await
+            .unwrap();
+        addr.send(Urgent).await.unwrap();
+
+        let (recorder, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(recorder.unwrap().0, vec!["low", "urgent"]);
+    }
+
+    async fn overflow(policy: OverflowPolicy) -> (Vec<Result<(), ActorError>>, Vec<&'static str>) {
+        let addr = crate::build(Recorder::default())
+            .bounded(2)
+            .overflow(policy)
+            .spawn_owning();
+
+        addr.send(Block(Duration::from_millis(50))).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let sender = addr.to_addr().sender::<Record>();
+        let results = ["a", "b", "c"]
+            .into_iter()
+            .map(|name| sender.force_send(Record(name)))
+            .collect();
+
+        let (recorder, _) = addr.stop_and_join().unwrap().await;
+        (results, recorder.unwrap().0)
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn rejects_when_full() {
+        let (results, recorded) = overflow(OverflowPolicy::Reject).await;
+        assert_eq!(results, vec![Ok(()), Ok(()), Err(ActorError::MailboxFull)]);
+        assert_eq!(recorded, vec!["a", "b"]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn blocking_force_send_fails_when_full() {
+        let (results, recorded) = overflow(OverflowPolicy::Block).await;
+        assert_eq!(results, vec![Ok(()), Ok(()), Err(ActorError::MailboxFull)]);
+        assert_eq!(recorded, vec!["a", "b"]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn drops_newest_when_full() {
+        let (results, recorded) = overflow(OverflowPolicy::DropNewest).await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(recorded, vec!["a", "b"]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn drops_oldest_when_full() {
+        let (results, recorded) = overflow(OverflowPolicy::DropOldest).await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(recorded, vec!["b", "c"]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn blocking_send_waits_for_room() {
+        let addr = crate::build(Recorder::default()).bounded(1).spawn_owning();
+
+        addr.send(Block(Duration::from_millis(50))).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        addr.send(Record("a")).await.unwrap();
+
+        let blocked = tokio::time::timeout(Duration::from_millis(10), addr.send(Record("b"))).await;
+        assert!(
+            blocked.is_err(),
+            "send should wait while the mailbox is full"
+        );
+        addr.send(Record("c")).await.unwrap();
+
+        let (recorder, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(recorder.unwrap().0, vec!["a", "c"]);
+    }
+
+    async fn report_progress(conflating: bool) -> Vec<&'static str> {
+        let builder = crate::build(Recorder::default()).bounded(10);
+        let addr = if conflating {
+            builder.conflating().spawn_owning()
+        } else {
+            builder.spawn_owning()
+        };
+
+        addr.send(Block(Duration::from_millis(50))).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        addr.send(Progress("download", "download 10%"))
+            .await
+            .unwrap();
+        addr.send(Progress("upload", "upload 50%")).await.unwrap();
+        addr.send(Record("unrelated")).await.unwrap();
+        addr.send(Progress("download", "download 20%"))
+            .await
+            .unwrap();
+
+        let (recorder, _) = addr.stop_and_join().unwrap().await;
+        recorder.unwrap().0
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn conflating_mailbox_keeps_latest_per_key() {
+        assert_eq!(
+            report_progress(true).await,
+            vec!["download 20%", "upload 50%", "unrelated"]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn fifo_mailbox_does_not_conflate() {
+        assert_eq!(
+            report_progress(false).await,
+            vec!["download 10%", "upload 50%", "unrelated", "download 20%"]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn reports_mailbox_stats() {
+        let addr = crate::build(Recorder::default())
+            .bounded(4)
+            .overflow(OverflowPolicy::DropNewest)
+            .spawn_owning();
+        assert_eq!(addr.mailbox_stats().capacity, Some(4));
+
+        addr.send(Block(Duration::from_millis(50))).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        for name in ["a", "b", "c", "d", "e"] {
+            addr.send(Record(name)).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let stats = addr.mailbox_stats();
+        assert_eq!(addr.mailbox_len(), 4);
+        assert_eq!(
+            MailboxStats {
+                oldest: None,
+                ..stats
+            },
+            MailboxStats {
+                len: 4,
+                capacity: Some(4),
+                enqueued: 5,
+                processed: 1,
+                dropped: 1,
+                oldest: None,
+            }
+        );
+        assert!(stats.oldest.unwrap() >= Duration::from_millis(10));
+
+        let weak = addr.to_addr().downgrade();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let stats = weak.mailbox_stats();
+        assert_eq!((stats.len, stats.processed, stats.oldest), (0, 5, None));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn priority_mailbox_stats_add_up() {
+        let addr = crate::build(Recorder::default())
+            .unbounded_priority()
+            .spawn_owning();
+
+        addr.send(Block(Duration::from_millis(50))).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        addr.send(Urgent).await.unwrap();
+        addr.send_with_priority(Record("low"), Priority::Low)
+            .await
+            .unwrap();
+
+        let stats = addr.mailbox_stats();
+        assert_eq!((stats.len, stats.capacity, stats.enqueued), (2, None, 3));
+    }
+}
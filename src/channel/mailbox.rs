@@ -0,0 +1,477 @@
+//! The mailbox behind every actor's channel.
+//!
+//! Only messages count towards the capacity,
+//! stopping, restarting and internal notifications are always queued.
+//! Bounded mailboxes handle overflow according to an [`OverflowPolicy`],
+//! conflating mailboxes replace queued messages in place.
+//! Priority mailboxes queue every [`Priority`] in its own lane, all of them share the capacity.
+//! Stopping and restarting goes ahead of all messages there.
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError},
+    task::{self, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use futures::stream::poll_fn;
+
+use crate::{
+    Priority,
+    context::ContextID,
+    dead_letters::{self, DeadLetter, DeadLetterReason},
+    environment::Payload,
+    error::{ActorError, Result},
+};
+
+use super::{Channel, ChannelConfig, PayloadStream};
+
+/// What a bounded mailbox does with a message that arrives while it is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until there is room again.
+    ///
+    /// Sending without waiting, e.g. via [`WeakSender::try_force_send`](`crate::WeakSender::try_force_send`),
+    /// fails with [`ActorError::MailboxFull`].
+    #[default]
+    Block,
+    /// Fail with [`ActorError::MailboxFull`].
+    Reject,
+    /// Silently drop the new message.
+    DropNewest,
+    /// Drop the oldest queued message to make room for the new one.
+    DropOldest,
+}
+
+/// A snapshot of an actor's mailbox, see [`Addr::mailbox_stats`](`crate::Addr::mailbox_stats`).
+///
+/// Only messages are counted, not the internal notifications of the actor system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MailboxStats {
+    /// Messages waiting to be handled.
+    pub len: usize,
+    /// How many messages fit into the mailbox, `None` if it is unbounded.
+    pub capacity: Option<usize>,
+    /// Messages that were accepted by the mailbox.
+    pub enqueued: u64,
+    /// Messages that were taken out of the mailbox to be handled.
+    pub processed: u64,
+    /// Messages that were dropped by the [`OverflowPolicy`], replaced by a [conflating](`crate::Conflate`) one
+    /// or removed because their [caller gave up](`crate::CallHandle`).
+    pub dropped: u64,
+    /// How long the oldest queued message has been waiting.
+    pub oldest: Option<Duration>,
+}
+
+struct Queued<A> {
+    since: Instant,
+    payload: Payload<A>,
+}
+
+/// Where a payload waits in the mailbox, the lanes are drained in this order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Lane {
+    Control,
+    High,
+    Normal,
+    Low,
+}
+
+/// The queues of a mailbox, a FIFO mailbox only uses the normal lane.
+struct Lanes<A> {
+    control: VecDeque<Queued<A>>,
+    high: VecDeque<Queued<A>>,
+    normal: VecDeque<Queued<A>>,
+    low: VecDeque<Queued<A>>,
+}
+
+impl<A> Default for Lanes<A> {
+    fn default() -> Self {
+        Self {
+            control: VecDeque::new(),
+            high: VecDeque::new(),
+            normal: VecDeque::new(),
+            low: VecDeque::new(),
+        }
+    }
+}
+
+impl<A> Lanes<A> {
+    const fn lane(&mut self, lane: Lane) -> &mut VecDeque<Queued<A>> {
+        match lane {
+            Lane::Control => &mut self.control,
+            Lane::High => &mut self.high,
+            Lane::Normal => &mut self.normal,
+            Lane::Low => &mut self.low,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Queued<A>> {
+        self.control
+            .iter()
+            .chain(&self.high)
+            .chain(&self.normal)
+            .chain(&self.low)
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut Queued<A>> {
+        self.control
+            .iter_mut()
+            .chain(&mut self.high)
+            .chain(&mut self.normal)
+            .chain(&mut self.low)
+    }
+
+    fn len(&self) -> usize {
+        self.control.len() + self.high.len() + self.normal.len() + self.low.len()
+    }
+
+    fn pop_front(&mut self) -> Option<Queued<A>> {
+        self.control
+            .pop_front()
+            .or_else(|| self.high.pop_front())
+            .or_else(|| self.normal.pop_front())
+            .or_else(|| self.low.pop_front())
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&Queued<A>) -> bool) {
+        for lane in [Lane::Control, Lane::High, Lane::Normal, Lane::Low] {
+            self.lane(lane).retain(&mut keep);
+        }
+    }
+
+    /// Remove the message that has been waiting the longest, whatever its priority.
+    fn remove_oldest_message(&mut self) -> Option<Queued<A>> {
+        let is_message = |queued: &Queued<A>| queued.payload.is_message();
+        // every lane is in order, so the oldest message is the first one of some lane
+        let (lane, _) = [Lane::High, Lane::Normal, Lane::Low]
+            .into_iter()
+            .filter_map(|lane| {
+                let first = self.lane(lane).iter().find(|queued| is_message(queued))?;
+                Some((lane, first.since))
+            })
+            .min_by_key(|&(_, since)| since)?;
+        let queue = self.lane(lane);
+        let index = queue.iter().position(is_message)?;
+        queue.remove(index)
+    }
+
+    fn into_payloads(self) -> impl Iterator<Item = Payload<A>> {
+        self.control
+            .into_iter()
+            .chain(self.high)
+            .chain(self.normal)
+            .chain(self.low)
+            .map(|queued| queued.payload)
+    }
+}
+
+struct State<A> {
+    lanes: Lanes<A>,
+    messages: usize,
+    enqueued: u64,
+    processed: u64,
+    dropped: u64,
+    receiver: Option<Waker>,
+    senders: Vec<Waker>,
+    receiver_dropped: bool,
+    senders_dropped: bool,
+}
+
+impl<A> State<A> {
+    /// Make room by removing the messages whose callers gave up on them.
+    fn remove_cancelled(&mut self) {
+        let before = self.lanes.len();
+        self.lanes.retain(|queued| !queued.payload.is_cancelled());
+        let removed = before - self.lanes.len();
+        self.messages -= removed;
+        self.dropped += removed as u64;
+    }
+}
+
+struct Mailbox<A> {
+    state: Mutex<State<A>>,
+    /// Shared by all lanes of a priority mailbox.
+    capacity: usize,
+    policy: OverflowPolicy,
+    conflate: bool,
+    priority: bool,
+    /// The actor that owns the mailbox, known once it is spawned.
+    owner: OnceLock<(ContextID, &'static str)>,
+}
+
+/// The outcome of trying to queue a payload.
+///
+/// Payloads that are not queued are handed back so they are reported and dropped after the lock is released.
+enum Push<A> {
+    Queued(Option<(Payload<A>, DeadLetterReason)>),
+    Wait(Payload<A>),
+    Failed(ActorError, Payload<A>),
+}
+
+impl<A> Mailbox<A> {
+    fn lock(&self) -> MutexGuard<'_, State<A>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    const fn lane(&self, payload: &Payload<A>) -> Lane {
+        if !self.priority {
+            return Lane::Normal;
+        }
+        if payload.is_control() {
+            return Lane::Control;
+        }
+        match payload.priority() {
+            Priority::High => Lane::High,
+            Priority::Normal => Lane::Normal,
+            Priority::Low => Lane::Low,
+        }
+    }
+
+    fn push(&self, payload: Payload<A>, waker: Option<&Waker>) -> Push<A> {
+        let mut state = self.lock();
+        if state.receiver_dropped {
+            return Push::Failed(ActorError::AlreadyStopped, payload);
+        }
+
+        if self.conflate {
+            let queued = payload.conflation_key().and_then(|key| {
+                state
+                    .lanes
+                    .iter_mut()
+                    .find(|queued| queued.payload.conflation_key() == Some(key))
+            });
+            if let Some(queued) = queued {
+                log::trace!("conflating message");
+                // keeps the original timestamp, the slot has been waiting that long
+                let replaced = std::mem::replace(&mut queued.payload, payload);
+                state.enqueued += 1;
+                state.dropped += 1;
+                return Push::Queued(Some((replaced, DeadLetterReason::Conflated)));
+            }
+        }
+
+        if payload.is_message() && state.messages >= self.capacity {
+            state.remove_cancelled();
+        }
+
+        let mut dropped = None;
+        if payload.is_message() && state.messages >= self.capacity {
+            match (self.policy, waker) {
+                (OverflowPolicy::Block, Some(waker)) => {
+                    state.senders.push(waker.clone());
+                    return Push::Wait(payload);
+                }
+                (OverflowPolicy::Block | OverflowPolicy::Reject, _) => {
+                    return Push::Failed(ActorError::MailboxFull, payload);
+                }
+                (OverflowPolicy::DropNewest, _) => {
+                    log::debug!("mailbox full, dropping newest message");
+                    state.dropped += 1;
+                    return Push::Queued(Some((payload, DeadLetterReason::Dropped)));
+                }
+                (OverflowPolicy::DropOldest, _) => {
+                    log::debug!("mailbox full, dropping oldest message");
+                    dropped = state
+                        .lanes
+                        .remove_oldest_message()
+                        .map(|queued| (queued.payload, DeadLetterReason::Dropped));
+                    if dropped.is_some() {
+                        state.messages -= 1;
+                        state.dropped += 1;
+                    }
+                }
+            }
+        }
+
+        if payload.is_message() {
+            state.messages += 1;
+            state.enqueued += 1;
+        }
+        let lane = self.lane(&payload);
+        state.lanes.lane(lane).push_back(Queued {
+            since: Instant::now(),
+            payload,
+        });
+        if let Some(receiver) = state.receiver.take() {
+            receiver.wake();
+        }
+        Push::Queued(dropped)
+    }
+
+    /// Report a message that is not going to be handled.
+    fn report(&self, payload: &Payload<A>, reason: DeadLetterReason) {
+        if let (Some(envelope), Some(&(target, actor))) = (payload.envelope(), self.owner.get()) {
+            dead_letters::report(DeadLetter {
+                target,
+                actor,
+                message: envelope.message,
+                reason,
+            });
+        }
+    }
+
+    fn poll_recv(&self, ctx: &mut task::Context<'_>) -> Poll<Option<Payload<A>>> {
+        let mut state = self.lock();
+        while let Some(Queued { payload, .. }) = state.lanes.pop_front() {
+            if payload.is_cancelled() {
+                log::trace!("skipping cancelled message");
+                state.messages -= 1;
+                state.dropped += 1;
+                state.senders.drain(..).for_each(Waker::wake);
+                continue;
+            }
+            if payload.is_message() {
+                state.messages -= 1;
+                state.processed += 1;
+                state.senders.drain(..).for_each(Waker::wake);
+            }
+            return Poll::Ready(Some(payload));
+        }
+        if state.senders_dropped {
+            return Poll::Ready(None);
+        }
+        state.receiver = Some(ctx.waker().clone());
+        Poll::Pending
+    }
+
+    fn stats(&self) -> MailboxStats {
+        let state = self.lock();
+        MailboxStats {
+            len: state.messages,
+            capacity: (self.capacity != usize::MAX).then_some(self.capacity),
+            enqueued: state.enqueued,
+            processed: state.processed,
+            dropped: state.dropped,
+            oldest: state
+                .lanes
+                .iter()
+                .filter(|queued| queued.payload.is_message())
+                .map(|queued| queued.since)
+                .min()
+                .map(|since| since.elapsed()),
+        }
+    }
+}
+
+struct SenderGuard<A>(Arc<Mailbox<A>>);
+
+impl<A> Drop for SenderGuard<A> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.senders_dropped = true;
+        if let Some(receiver) = state.receiver.take() {
+            receiver.wake();
+        }
+    }
+}
+
+struct ReceiverGuard<A>(Arc<Mailbox<A>>);
+
+impl<A> Drop for ReceiverGuard<A> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.receiver_dropped = true;
+        state.messages = 0;
+        let lanes = std::mem::take(&mut state.lanes);
+        let senders = std::mem::take(&mut state.senders);
+        drop(state);
+
+        for payload in lanes.into_payloads() {
+            self.0.report(&payload, DeadLetterReason::ActorStopped);
+        }
+        senders.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<A> Channel<A>
+where
+    for<'a> A: 'a,
+{
+    /// A mailbox set up according to `config`.
+    pub(super) fn mailbox(config: ChannelConfig) -> Self {
+        let mailbox = Arc::new(Mailbox {
+            state: Mutex::new(State {
+                lanes: Lanes::default(),
+                messages: 0,
+                enqueued: 0,
+                processed: 0,
+                dropped: 0,
+                receiver: None,
+                senders: Vec::new(),
+                receiver_dropped: false,
+                senders_dropped: false,
+            }),
+            capacity: config.capacity.unwrap_or(usize::MAX).max(1),
+            policy: config.overflow,
+            conflate: config.conflate,
+            priority: config.priority,
+            owner: OnceLock::new(),
+        });
+
+        let guard = Arc::new(SenderGuard(Arc::clone(&mailbox)));
+        let force_guard = Arc::clone(&guard);
+
+        let send = Arc::new(
+            move |event: Payload<A>| -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+                let mailbox = Arc::clone(&guard.0);
+                let mut event = Some(event);
+                Box::pin(futures::future::poll_fn(move |ctx| {
+                    let Some(payload) = event.take() else {
+                        return Poll::Ready(Ok(()));
+                    };
+                    match mailbox.push(payload, Some(ctx.waker())) {
+                        Push::Queued(dropped) => {
+                            if let Some((payload, reason)) = dropped {
+                                mailbox.report(&payload, reason);
+                            }
+                            Poll::Ready(Ok(()))
+                        }
+                        Push::Wait(payload) => {
+                            event = Some(payload);
+                            Poll::Pending
+                        }
+                        Push::Failed(error, payload) => {
+                            mailbox.report(&payload, DeadLetterReason::from_error(&error));
+                            Poll::Ready(Err(error))
+                        }
+                    }
+                }))
+            },
+        );
+
+        let force_send = Arc::new(move |event: Payload<A>| -> Result<()> {
+            let mailbox = &force_guard.0;
+            match mailbox.push(event, None) {
+                Push::Queued(dropped) => {
+                    if let Some((payload, reason)) = dropped {
+                        mailbox.report(&payload, reason);
+                    }
+                    Ok(())
+                }
+                Push::Wait(payload) => {
+                    mailbox.report(&payload, DeadLetterReason::MailboxFull);
+                    Err(ActorError::MailboxFull)
+                }
+                Push::Failed(error, payload) => {
+                    mailbox.report(&payload, DeadLetterReason::from_error(&error));
+                    Err(error)
+                }
+            }
+        });
+
+        let stats_mailbox = Arc::clone(&mailbox);
+        let stats = Arc::new(move || stats_mailbox.stats());
+
+        let owned_mailbox = Arc::clone(&mailbox);
+        let bind = Arc::new(move |id, actor| {
+            let _ = owned_mailbox.owner.set((id, actor));
+        });
+
+        let receiver = ReceiverGuard(mailbox);
+        let recv: PayloadStream<A> = poll_fn(Box::new(move |ctx| receiver.0.poll_recv(ctx)));
+
+        Self::new(send, force_send, recv, stats, bind)
+    }
+}
@@ -1,20 +1,41 @@
 use futures::channel::oneshot;
+use std::sync::Arc;
 
+#[cfg_attr(
+    not(any(feature = "tokio", feature = "async-std", feature = "custom_runtime")),
+    allow(unused_imports)
+)]
+use crate::Handler;
 use crate::{
-    Addr, Handler, RestartableActor, Sender, WeakAddr,
-    actor::{Actor, spawner::Spawner},
-    channel::{WeakChanTx, WeakForceChanTx},
+    Addr, RestartableActor, Sender, WeakAddr,
+    actor::{Actor, StopReason},
+    channel::{MailboxStatsFn, WeakChanTx, WeakForceChanTx},
     environment::Payload,
     error::{ActorError::AlreadyStopped, Result},
-    prelude::Spawnable,
 };
 pub use id::ContextID;
 
-pub type RunningFuture = futures::future::Shared<oneshot::Receiver<()>>;
-pub struct StopNotifier(pub(crate) oneshot::Sender<()>);
+pub(crate) mod behavior;
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "custom_runtime"))]
+mod death_watch;
+mod linking;
+mod stashing;
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "custom_runtime"))]
+mod stream_handling;
+mod supervision;
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "custom_runtime"))]
+pub use death_watch::Terminated;
+pub use linking::Exit;
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "custom_runtime"))]
+pub use stream_handling::StreamHandle;
+pub(crate) use supervision::Supervision;
+pub use supervision::SupervisionStrategy;
+
+pub type RunningFuture = futures::future::Shared<oneshot::Receiver<StopReason>>;
+pub struct StopNotifier(pub(crate) oneshot::Sender<StopReason>);
 impl StopNotifier {
-    pub fn notify(self) {
-        self.0.send(()).ok();
+    pub fn notify(self, reason: StopReason) {
+        self.0.send(reason).ok();
     }
 }
 
@@ -57,9 +78,41 @@ pub struct Context<A> {
     #[allow(dead_code)]
     pub(crate) weak_tx: WeakChanTx<A>,
     pub(crate) weak_force_tx: WeakForceChanTx<A>,
+    pub(crate) mailbox: MailboxStatsFn,
     pub(crate) running: RunningFuture,
     pub(crate) children: Vec<Sender<()>>,
-    pub(crate) tasks: Vec<futures::future::AbortHandle>,
+    pub(crate) supervision: Supervision,
+    pub(crate) tasks: Vec<Task>,
+    #[cfg_attr(
+        not(any(feature = "tokio", feature = "async-std", feature = "custom_runtime")),
+        allow(dead_code)
+    )]
+    pub(crate) watching: std::collections::HashMap<ContextID, futures::future::AbortHandle>,
+    #[cfg_attr(
+        not(any(feature = "tokio", feature = "async-std", feature = "custom_runtime")),
+        allow(dead_code)
+    )]
+    pub(crate) links: std::collections::HashMap<ContextID, futures::future::AbortHandle>,
+    pub(crate) trap_exit: Option<linking::ExitHandler<A>>,
+    pub(crate) stash: stashing::Stash<A>,
+    pub(crate) behaviors: behavior::Behaviors<A>,
+}
+
+/// A task spawned by the actor, aborted when the actor stops.
+pub(crate) struct Task {
+    abort: futures::future::AbortHandle,
+    /// Dropped along with the task's future, once it finished or was aborted.
+    #[cfg_attr(
+        not(any(feature = "tokio", feature = "async-std", feature = "custom_runtime")),
+        allow(dead_code)
+    )]
+    alive: std::sync::Weak<()>,
+}
+
+impl Task {
+    fn abort(&self) {
+        self.abort.abort();
+    }
 }
 
 impl<A> Drop for Context<A> {
@@ -87,21 +140,10 @@ impl<A: Actor> Context<A> {
     /// Add a child actor to the context.
     ///
     /// The child actor will be stopped when the parent actor is stopped.
+    /// Unlike children created with [`Context::create_child`] it is not supervised.
     pub fn add_child(&mut self, child: impl Into<Sender<()>>) {
         self.children.push(child.into());
     }
-
-    /// Create a child actor and add it to the context.
-    pub fn create_child<F, C, S>(&mut self, create_child: F)
-    where
-        F: FnOnce() -> C,
-        C: Actor + Spawnable<S>,
-        C: Handler<()>,
-        S: Spawner<C>,
-    {
-        let child_addr = create_child().spawn();
-        self.add_child(child_addr);
-    }
 }
 
 impl<A: Actor> Context<A> {
@@ -121,6 +163,7 @@ impl<A: Actor> Context<A> {
             context_id: self.id,
             payload_tx,
             payload_force_tx,
+            mailbox: Arc::clone(&self.mailbox),
             running: self.running.clone(),
         })
     }
@@ -148,7 +191,11 @@ impl<A: Actor> Context<A> {
     where
         A: Handler<M>,
     {
-        crate::WeakCaller::from_weak_tx(std::sync::Weak::clone(&self.weak_tx), self.id)
+        crate::WeakCaller::from_weak_tx(
+            std::sync::Weak::clone(&self.weak_tx),
+            self.id,
+            self.running.clone(),
+        )
     }
 }
 
@@ -176,6 +223,20 @@ impl<A: Actor> Context<A> {
     {
         crate::Broker::subscribe(self.weak_sender()).await
     }
+
+    /// Subscribe to a message on a specific broker instead of the global one.
+    ///
+    /// See [`Broker::new`](`crate::Broker::new`).
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub async fn subscribe_to<M: crate::Message<Response = ()> + Clone>(
+        &mut self,
+        broker: &crate::Addr<crate::Broker<M>>,
+    ) -> Result<()>
+    where
+        A: Handler<M>,
+    {
+        broker.subscribe(self.weak_sender()).await
+    }
 }
 
 #[cfg(any(feature = "tokio", feature = "async-std", feature = "custom_runtime"))]
@@ -185,19 +246,34 @@ mod task_handling {
 
     use crate::{Context, Handler, Message, actor::Actor, spawner::SpawnSelf};
 
+    impl super::Task {
+        fn is_running(&self) -> bool {
+            self.alive.strong_count() > 0 && !self.abort.is_aborted()
+        }
+    }
+
     /// Task Handling
     impl<A: Actor> Context<A> {
-        fn spawn_task(&mut self, task: impl Future<Output = ()> + Send + 'static) {
+        pub(crate) fn spawn_task(
+            &mut self,
+            task: impl Future<Output = ()> + Send + 'static,
+        ) -> futures::future::AbortHandle {
             let (task, handle) = futures::future::abortable(task);
-
-            self.tasks.push(handle);
-            A::spawn_future(task.map(|_| ()))
+            let alive = std::sync::Arc::new(());
+
+            self.tasks.retain(super::Task::is_running);
+            self.tasks.push(super::Task {
+                abort: handle.clone(),
+                alive: std::sync::Arc::downgrade(&alive),
+            });
+            A::spawn_future(task.map(move |_| drop(alive)));
+            handle
         }
 
         #[cfg(test)]
         pub(crate) fn stop_tasks(&mut self) {
-            for handle in self.tasks.drain(..) {
-                handle.abort();
+            for task in self.tasks.drain(..) {
+                task.abort();
             }
         }
 
@@ -213,11 +289,15 @@ mod task_handling {
             self.spawn_task(async move {
                 loop {
                     A::sleep(duration).await;
-                    if myself.try_force_send(message.clone()).is_err() {
-                        break;
+                    match myself.try_force_send(message.clone()) {
+                        Ok(()) => {}
+                        Err(crate::error::ActorError::MailboxFull) => {
+                            log::debug!("{} mailbox full, skipping tick", A::NAME);
+                        }
+                        Err(_) => break,
                     }
                 }
-            })
+            });
         }
 
         /// Send yourself a message at a regular interval.
@@ -232,11 +312,15 @@ mod task_handling {
             self.spawn_task(async move {
                 loop {
                     A::sleep(duration).await;
-                    if myself.try_send(message_fn()).await.is_err() {
-                        break;
+                    match myself.try_send(message_fn()).await {
+                        Ok(()) => {}
+                        Err(crate::error::ActorError::MailboxFull) => {
+                            log::debug!("{} mailbox full, skipping tick", A::NAME);
+                        }
+                        Err(_) => break,
                     }
                 }
-            })
+            });
         }
 
         /// Send yourself a message after a delay.
@@ -251,10 +335,11 @@ mod task_handling {
             self.spawn_task(async move {
                 A::sleep(duration).await;
 
-                if myself.try_send(message_fn()).await.is_err() {
-                    log::warn!("Failed to send message");
+                // undelivered messages are reported as dead letters
+                if let Err(error) = myself.try_send(message_fn()).await {
+                    log::warn!("Failed to send message: {error}");
                 }
-            })
+            });
         }
     }
 }
@@ -298,7 +383,7 @@ mod interval_cleanup {
         }
 
         impl Actor for IntervalActor {
-            async fn stopped(&mut self, _: &mut Context<Self>) {
+            async fn stopped(&mut self, _: &mut Context<Self>, _: StopReason) {
                 self.running.store(false, Ordering::SeqCst);
             }
         }
@@ -337,17 +422,20 @@ mod interval_cleanup {
 
         static ATOMIC_VEC: LazyLock<Mutex<Vec<String>>> = LazyLock::new(|| Mutex::new(Vec::new()));
 
+        /// Handlers that are currently running, and the most that ever ran at once.
+        static RUNNING: AtomicU32 = AtomicU32::new(0);
+        static MAX_RUNNING: AtomicU32 = AtomicU32::new(0);
+
         fn append_to_log(item: impl Into<String>) {
             let vec = &*ATOMIC_VEC;
             vec.lock().unwrap().push(item.into());
         }
 
-        fn print_log() -> usize {
+        fn print_log() {
             let vec = &*ATOMIC_VEC.lock().unwrap();
             for (i, line) in vec.iter().enumerate() {
                 eprintln!("Log {i:?}: {line:?}");
             }
-            vec.len()
         }
 
         #[derive(Debug)]
@@ -375,6 +463,8 @@ mod interval_cleanup {
                 IntervalSleep(duration, invocation): IntervalSleep,
             ) {
                 let call_id = ContextID::default();
+                let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+                MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
 
                 let called = Instant::now();
                 let called_after_ms = called.duration_since(self.started).as_millis();
@@ -389,6 +479,7 @@ mod interval_cleanup {
                 append_to_log(format!(
                     "handler {call_id}/{invocation} wakeup {woke_after_ms}ms"
                 ));
+                RUNNING.fetch_sub(1, Ordering::SeqCst);
             }
         }
 
@@ -419,7 +510,7 @@ mod interval_cleanup {
                 );
                 Ok(())
             }
-            async fn stopped(&mut self, _: &mut Context<Self>) {
+            async fn stopped(&mut self, _: &mut Context<Self>, _: StopReason) {
                 append_to_log("stopped");
             }
         }
@@ -437,8 +528,13 @@ mod interval_cleanup {
             sleep(Duration::from_millis(600)).await;
 
             addr.stop_and_join().await.unwrap();
-            let log_length = print_log();
-            assert_eq!(log_length, 12);
+            print_log();
+            assert_eq!(
+                MAX_RUNNING.load(Ordering::SeqCst),
+                1,
+                "handlers must not overlap"
+            );
+            assert_eq!(RUNNING.load(Ordering::SeqCst), 0);
         }
     }
 
@@ -456,7 +552,7 @@ mod interval_cleanup {
                 ctx.interval_with(|| (), Duration::from_millis(100));
                 Ok(())
             }
-            async fn stopped(&mut self, _: &mut Context<Self>) {
+            async fn stopped(&mut self, _: &mut Context<Self>, _: StopReason) {
                 self.running.store(false, Ordering::SeqCst);
             }
         }
@@ -482,6 +578,37 @@ mod interval_cleanup {
                 "Handler should not be called after actor is stopped"
             );
         }
+
+        #[derive(Debug, Default)]
+        struct SlowTicks(u32);
+
+        impl Actor for SlowTicks {
+            async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult<()> {
+                ctx.interval_with(|| (), Duration::from_millis(10));
+                Ok(())
+            }
+        }
+
+        impl Handler<()> for SlowTicks {
+            async fn handle(&mut self, _: &mut Context<Self>, _: ()) {
+                sleep(Duration::from_millis(35)).await;
+                self.0 += 1;
+            }
+        }
+
+        #[tokio::test]
+        async fn skips_ticks_while_mailbox_full() {
+            let addr = crate::build(SlowTicks::default())
+                .bounded(1)
+                .overflow(crate::OverflowPolicy::Reject)
+                .spawn_owning();
+            sleep(Duration::from_millis(300)).await;
+            let ticks = addr.consume().await.unwrap().0;
+            assert!(
+                ticks >= 4,
+                "interval must survive a full mailbox, got {ticks} ticks"
+            );
+        }
     }
 
     mod delayed_send {
@@ -498,7 +625,7 @@ mod interval_cleanup {
                 ctx.delayed_send(|| (), Duration::from_millis(100));
                 Ok(())
             }
-            async fn stopped(&mut self, _: &mut Context<Self>) {
+            async fn stopped(&mut self, _: &mut Context<Self>, _: StopReason) {
                 self.running.store(false, Ordering::SeqCst);
             }
         }
@@ -509,6 +636,30 @@ mod interval_cleanup {
             }
         }
 
+        struct SendLater;
+        impl Message for SendLater {
+            type Response = usize;
+        }
+
+        impl Handler<SendLater> for DelayedSendActor {
+            async fn handle(&mut self, ctx: &mut Context<Self>, _: SendLater) -> usize {
+                ctx.delayed_send(|| (), Duration::from_millis(1));
+                ctx.tasks.len()
+            }
+        }
+
+        #[tokio::test]
+        async fn finished_tasks_are_pruned() {
+            let addr = DelayedSendActor {
+                running: Arc::default(),
+            }
+            .spawn();
+            for _ in 0..20 {
+                assert!(addr.call(SendLater).await.unwrap() <= 2);
+                sleep(Duration::from_millis(10)).await;
+            }
+        }
+
         #[tokio::test]
         async fn stopped_when_actor_stopped() {
             let running = Arc::new(AtomicBool::new(false));
@@ -0,0 +1,526 @@
+//! Swapping the set of handlers that an actor uses for its messages.
+//!
+//! Instead of matching on the actor's state in every [`Handler`], a state machine can be split into [`Behavior`]s.
+//! The active behavior handles the messages it [registered](`Behavior::handles`),
+//! every other message is handled by the actor itself or discarded, see [`Unhandled`].
+//! Messages that the actor does not handle itself are sent via [`Addr::send_to_behavior`](`crate::Addr::send_to_behavior`)
+//! and [`Addr::call_behavior`](`crate::Addr::call_behavior`).
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+};
+
+use crate::{Context, DeadLetter, DeadLetterReason, Handler, Message, actor::Actor};
+
+/// What happens to messages that the active [`Behavior`] does not handle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Unhandled {
+    /// The actor's own [`Handler`] handles the message.
+    #[default]
+    Fallback,
+    /// The message is discarded and reported as a [`DeadLetter`], callers receive [`ActorError::Canceled`](`crate::error::ActorError::Canceled`).
+    DeadLetters,
+}
+
+/// A set of message handlers that an actor can switch to via [`Context::become_behavior`].
+///
+/// ```no_run
+/// # use hannibal::{prelude::*, Behavior, BehaviorHandler, Handles};
+/// #[derive(Debug, Message)]
+/// struct Connect(u16);
+///
+/// #[derive(Debug, Message)]
+/// struct Transmit(&'static str);
+///
+/// #[derive(Default)]
+/// struct Client;
+/// impl Actor for Client {}
+///
+/// // the client handles `Connect` and ignores `Transmit` until it is connected
+/// impl Handler<Connect> for Client {
+///     async fn handle(&mut self, ctx: &mut Context<Self>, Connect(port): Connect) {
+///         ctx.become_behavior(Connected { port });
+///     }
+/// }
+/// impl Handler<Transmit> for Client {
+///     async fn handle(&mut self, _: &mut Context<Self>, _: Transmit) {}
+/// }
+///
+/// struct Connected {
+///     port: u16,
+/// }
+///
+/// impl Behavior<Client> for Connected {
+///     fn handles(handles: &mut Handles<Client, Self>) {
+///         handles.on::<Transmit>();
+///     }
+/// }
+///
+/// impl BehaviorHandler<Client, Transmit> for Connected {
+///     async fn handle(&mut self, _: &mut Client, _: &mut Context<Client>, Transmit(data): Transmit) {
+///         println!("sending {data} to port {}", self.port);
+///     }
+/// }
+/// ```
+pub trait Behavior<A: Actor>: Send + Sized + 'static {
+    /// What happens to messages that this behavior does not handle.
+    const UNHANDLED: Unhandled = Unhandled::Fallback;
+
+    /// Register the messages that this behavior handles.
+    fn handles(handles: &mut Handles<A, Self>);
+}
+
+/// Handle a message while the behavior is active.
+pub trait BehaviorHandler<A: Actor, M: Message>: Behavior<A> {
+    /// Handle a message instead of the actor's own [`Handler`].
+    fn handle(
+        &mut self,
+        actor: &mut A,
+        ctx: &mut Context<A>,
+        msg: M,
+    ) -> impl Future<Output = M::Response> + Send;
+}
+
+type DispatchFn<A, M> =
+    for<'a> fn(
+        &'a mut (dyn Any + Send),
+        &'a mut A,
+        &'a mut Context<A>,
+        M,
+    ) -> Pin<Box<dyn Future<Output = Option<<M as Message>::Response>> + Send + 'a>>;
+
+fn dispatch_to<'a, A, B, M>(
+    behavior: &'a mut (dyn Any + Send),
+    actor: &'a mut A,
+    ctx: &'a mut Context<A>,
+    msg: M,
+) -> Pin<Box<dyn Future<Output = Option<M::Response>> + Send + 'a>>
+where
+    A: Actor,
+    B: BehaviorHandler<A, M>,
+    M: Message,
+{
+    Box::pin(async move {
+        let behavior = behavior.downcast_mut::<B>()?;
+        Some(behavior.handle(actor, ctx, msg).await)
+    })
+}
+
+/// The messages that a [`Behavior`] handles.
+pub struct Handles<A, B> {
+    handlers: HashMap<TypeId, Box<dyn Any + Send>>,
+    phantom: PhantomData<fn(A, B)>,
+}
+
+impl<A: Actor, B: Behavior<A>> Handles<A, B> {
+    /// Handle `M` with the behavior's [`BehaviorHandler`].
+    pub fn on<M: Message>(&mut self) -> &mut Self
+    where
+        B: BehaviorHandler<A, M>,
+    {
+        let dispatch: DispatchFn<A, M> = dispatch_to::<A, B, M>;
+        self.handlers.insert(TypeId::of::<M>(), Box::new(dispatch));
+        self
+    }
+}
+
+struct ActiveBehavior {
+    behavior: Box<dyn Any + Send>,
+    handlers: HashMap<TypeId, Box<dyn Any + Send>>,
+    unhandled: Unhandled,
+    id: TypeId,
+    name: &'static str,
+}
+
+/// The behavior stack, the last entry is the active behavior.
+///
+/// While the active behavior handles a message it is taken out and its slot is `None`.
+pub(crate) struct Behaviors<A> {
+    stack: Vec<Option<ActiveBehavior>>,
+    handling: Option<TypeId>,
+    phantom: PhantomData<fn(A)>,
+}
+
+impl<A> Default for Behaviors<A> {
+    fn default() -> Self {
+        Self {
+            stack: Vec::new(),
+            handling: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Where a message ends up.
+enum Route<A, M: Message> {
+    Actor,
+    Behavior(usize, ActiveBehavior, DispatchFn<A, M>),
+    DeadLetter(&'static str),
+}
+
+impl<A: Actor> Behaviors<A> {
+    fn route<M: Message>(&mut self) -> Route<A, M> {
+        let index = self.stack.len().saturating_sub(1);
+        let Some(slot) = self.stack.last_mut() else {
+            return Route::Actor;
+        };
+        let Some(active) = slot.as_ref() else {
+            return Route::Actor;
+        };
+
+        let dispatch = active
+            .handlers
+            .get(&TypeId::of::<M>())
+            .and_then(|dispatch| dispatch.downcast_ref::<DispatchFn<A, M>>())
+            .copied();
+        match (dispatch, active.unhandled) {
+            (Some(dispatch), _) => match slot.take() {
+                Some(active) => {
+                    self.handling = Some(active.id);
+                    Route::Behavior(index, active, dispatch)
+                }
+                None => Route::Actor,
+            },
+            (None, Unhandled::Fallback) => Route::Actor,
+            (None, Unhandled::DeadLetters) => Route::DeadLetter(active.name),
+        }
+    }
+
+    /// Put the behavior back, unless it was replaced or dropped while it was handling a message.
+    fn restore(&mut self, index: usize, active: ActiveBehavior) {
+        self.handling = None;
+        if let Some(slot @ None) = self.stack.get_mut(index) {
+            *slot = Some(active);
+        }
+    }
+}
+
+/// Routes a message to the active behavior, or elsewhere if the behavior does not handle it.
+///
+/// Returns `None` if the message was discarded.
+pub(crate) type Dispatcher<A, M> =
+    for<'a> fn(
+        &'a mut A,
+        &'a mut Context<A>,
+        M,
+    ) -> Pin<Box<dyn Future<Output = Option<<M as Message>::Response>> + Send + 'a>>;
+
+/// What the behavior table did with a message.
+enum Dispatched<M: Message> {
+    /// The active behavior handled the message, or discarded it.
+    Done(Option<M::Response>),
+    /// No behavior is active or the active one falls back to the actor.
+    Fallback(M),
+}
+
+async fn dispatch_to_behavior<A: Actor, M: Message>(
+    actor: &mut A,
+    ctx: &mut Context<A>,
+    msg: M,
+) -> Dispatched<M> {
+    match ctx.behaviors.route::<M>() {
+        Route::Actor => Dispatched::Fallback(msg),
+        Route::Behavior(index, mut active, dispatch) => {
+            let response = dispatch(active.behavior.as_mut(), actor, ctx, msg).await;
+            ctx.behaviors.restore(index, active);
+            Dispatched::Done(response)
+        }
+        Route::DeadLetter(behavior) => {
+            discard::<A, M>(ctx, behavior);
+            Dispatched::Done(None)
+        }
+    }
+}
+
+fn discard<A: Actor, M: Message>(ctx: &Context<A>, behavior: &str) {
+    log::warn!(
+        "{} discarded {} in behavior {behavior}",
+        A::NAME,
+        std::any::type_name::<M>()
+    );
+    crate::dead_letters::report(DeadLetter {
+        target: ctx.id,
+        actor: A::NAME,
+        message: std::any::type_name::<M>(),
+        reason: DeadLetterReason::Unhandled,
+    });
+}
+
+/// Handle a message with the active behavior or the actor itself.
+pub(crate) fn dispatch<'a, A, M>(
+    actor: &'a mut A,
+    ctx: &'a mut Context<A>,
+    msg: M,
+) -> Pin<Box<dyn Future<Output = Option<M::Response>> + Send + 'a>>
+where
+    A: Handler<M>,
+    M: Message,
+{
+    Box::pin(async move {
+        match dispatch_to_behavior(actor, ctx, msg).await {
+            Dispatched::Done(response) => response,
+            Dispatched::Fallback(msg) => Some(Handler::handle(actor, ctx, msg).await),
+        }
+    })
+}
+
+/// Handle a message that only behaviors handle, it is discarded if the active behavior does not.
+pub(crate) fn dispatch_to_behaviors<'a, A, M>(
+    actor: &'a mut A,
+    ctx: &'a mut Context<A>,
+    msg: M,
+) -> Pin<Box<dyn Future<Output = Option<M::Response>> + Send + 'a>>
+where
+    A: Actor,
+    M: Message,
+{
+    Box::pin(async move {
+        match dispatch_to_behavior(actor, ctx, msg).await {
+            Dispatched::Done(response) => response,
+            Dispatched::Fallback(_) => {
+                discard::<A, M>(ctx, "none");
+                None
+            }
+        }
+    })
+}
+
+/// Behaviors
+impl<A: Actor> Context<A> {
+    /// Replace the active behavior, or start using one.
+    ///
+    /// `become` is a reserved keyword, hence the longer name.
+    pub fn become_behavior<B: Behavior<A>>(&mut self, behavior: B) {
+        self.behaviors.stack.pop();
+        self.push_behavior(behavior);
+    }
+
+    /// Switch to a behavior and keep the active one to return to via [`unbecome`](`Context::unbecome`).
+    pub fn push_behavior<B: Behavior<A>>(&mut self, behavior: B) {
+        let mut handles = Handles::<A, B> {
+            handlers: HashMap::new(),
+            phantom: PhantomData,
+        };
+        B::handles(&mut handles);
+        self.behaviors.stack.push(Some(ActiveBehavior {
+            behavior: Box::new(behavior),
+            handlers: handles.handlers,
+            unhandled: B::UNHANDLED,
+            id: TypeId::of::<B>(),
+            name: std::any::type_name::<B>(),
+        }));
+    }
+
+    /// Drop the active behavior and return to the previous one, or to the actor's own handlers.
+    pub fn unbecome(&mut self) {
+        self.behaviors.stack.pop();
+    }
+
+    /// Returns `true` if `B` is the active behavior.
+    pub fn is_behaving_as<B: Behavior<A>>(&self) -> bool {
+        // while handling a message the active behavior is taken out of the stack
+        let id = TypeId::of::<B>();
+        match self.behaviors.stack.last() {
+            Some(Some(active)) => active.id == id,
+            Some(None) => self.behaviors.handling == Some(id),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use crate::{error::ActorError, prelude::*};
+
+    use super::{Behavior, BehaviorHandler, Handles, Unhandled};
+
+    #[derive(Debug, Default)]
+    struct Door {
+        log: Vec<&'static str>,
+    }
+    impl Actor for Door {}
+    impl crate::RestartableActor for Door {}
+
+    struct Knock;
+    impl Message for Knock {
+        type Response = &'static str;
+    }
+
+    struct Lock;
+    impl Message for Lock {
+        type Response = ();
+    }
+
+    struct Unlock;
+    impl Message for Unlock {
+        type Response = ();
+    }
+
+    struct Seal;
+    impl Message for Seal {
+        type Response = ();
+    }
+
+    /// Only handled while the door is [`Locked`], the door itself does not implement `Handler<Ring>`.
+    struct Ring;
+    impl Message for Ring {
+        type Response = &'static str;
+    }
+
+    struct IsLocked;
+    impl Message for IsLocked {
+        type Response = bool;
+    }
+
+    impl Handler<Knock> for Door {
+        async fn handle(&mut self, _: &mut Context<Self>, _: Knock) -> &'static str {
+            "come in"
+        }
+    }
+
+    impl Handler<Lock> for Door {
+        async fn handle(&mut self, ctx: &mut Context<Self>, _: Lock) {
+            self.log.push("locking");
+            ctx.push_behavior(Locked);
+        }
+    }
+
+    impl Handler<Unlock> for Door {
+        async fn handle(&mut self, _: &mut Context<Self>, _: Unlock) {
+            self.log.push("already unlocked");
+        }
+    }
+
+    impl Handler<Seal> for Door {
+        async fn handle(&mut self, ctx: &mut Context<Self>, _: Seal) {
+            ctx.become_behavior(Sealed);
+        }
+    }
+
+    impl Handler<IsLocked> for Door {
+        async fn handle(&mut self, ctx: &mut Context<Self>, _: IsLocked) -> bool {
+            ctx.is_behaving_as::<Locked>()
+        }
+    }
+
+    struct Locked;
+
+    impl Behavior<Door> for Locked {
+        fn handles(handles: &mut Handles<Door, Self>) {
+            handles.on::<Knock>().on::<Unlock>().on::<Ring>();
+        }
+    }
+
+    impl BehaviorHandler<Door, Knock> for Locked {
+        async fn handle(&mut self, _: &mut Door, _: &mut Context<Door>, _: Knock) -> &'static str {
+            "go away"
+        }
+    }
+
+    impl BehaviorHandler<Door, Unlock> for Locked {
+        async fn handle(&mut self, door: &mut Door, ctx: &mut Context<Door>, _: Unlock) {
+            assert!(ctx.is_behaving_as::<Self>());
+            door.log.push("unlocking");
+            ctx.unbecome();
+        }
+    }
+
+    impl BehaviorHandler<Door, Ring> for Locked {
+        async fn handle(&mut self, _: &mut Door, _: &mut Context<Door>, _: Ring) -> &'static str {
+            "who is it?"
+        }
+    }
+
+    struct Sealed;
+
+    impl Behavior<Door> for Sealed {
+        const UNHANDLED: Unhandled = Unhandled::DeadLetters;
+        fn handles(_: &mut Handles<Door, Self>) {}
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn switches_behaviors() {
+        let addr = Door::default().spawn_owning();
+
+        assert_eq!(addr.call(Knock).await.unwrap(), "come in");
+        addr.call(Lock).await.unwrap();
+        assert!(addr.call(IsLocked).await.unwrap());
+        assert_eq!(addr.call(Knock).await.unwrap(), "go away");
+
+        addr.call(Unlock).await.unwrap();
+        assert!(!addr.call(IsLocked).await.unwrap());
+        assert_eq!(addr.call(Knock).await.unwrap(), "come in");
+        addr.call(Unlock).await.unwrap();
+
+        let (door, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(
+            door.unwrap().log,
+            vec!["locking", "unlocking", "already unlocked"]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn unhandled_messages_fall_back_to_the_actor() {
+        let addr = Door::default().spawn_owning();
+
+        addr.call(Lock).await.unwrap();
+        addr.call(Lock).await.unwrap();
+        assert!(addr.call(IsLocked).await.unwrap());
+
+        let (door, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(door.unwrap().log, vec!["locking", "locking"]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn unhandled_messages_can_be_dead_letters() {
+        let addr = Door::default().spawn_owning();
+
+        addr.call(Seal).await.unwrap();
+        assert!(matches!(
+            addr.call(Knock).await,
+            Err(ActorError::Canceled(_))
+        ));
+        addr.send(Lock).await.unwrap();
+
+        let (door, _) = addr.stop_and_join().unwrap().await;
+        assert!(door.unwrap().log.is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn messages_can_be_handled_by_behaviors_only() {
+        let addr = Door::default().spawn_owning();
+
+        assert!(matches!(
+            addr.call_behavior(Ring).await,
+            Err(ActorError::Canceled(_))
+        ));
+        addr.call(Lock).await.unwrap();
+        assert_eq!(addr.call_behavior(Ring).await.unwrap(), "who is it?");
+        assert_eq!(addr.call_behavior(Knock).await.unwrap(), "go away");
+        addr.send_to_behavior(Unlock).await.unwrap();
+        assert!(!addr.call(IsLocked).await.unwrap());
+
+        let (door, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(door.unwrap().log, vec!["locking", "unlocking"]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn recreated_actor_starts_without_behaviors() {
+        let mut addr = crate::build(Door::default())
+            .unbounded()
+            .recreate_from_default()
+            .spawn();
+
+        addr.call(Lock).await.unwrap();
+        assert!(addr.call(IsLocked).await.unwrap());
+
+        addr.restart().unwrap();
+        assert!(!addr.call(IsLocked).await.unwrap());
+        assert_eq!(addr.call(Knock).await.unwrap(), "come in");
+    }
+}
@@ -0,0 +1,162 @@
+//! Monitoring other actors.
+//!
+//! A watching actor is told when a watched actor stops, without keeping it alive.
+use crate::{Addr, Context, Handler, Message, StopReason, actor::Actor, environment::Payload};
+
+use super::ContextID;
+
+/// Delivered to an actor when an actor it [watches](`Context::watch`) has stopped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Terminated {
+    /// The id of the actor that stopped, see [`Addr::id`].
+    pub id: ContextID,
+    /// Why the actor stopped.
+    pub reason: StopReason,
+}
+
+impl Message for Terminated {
+    type Response = ();
+}
+
+/// Death Watch
+impl<A: Actor> Context<A> {
+    /// Get notified when another actor stops.
+    ///
+    /// Once the watched actor has stopped, a [`Terminated`] message is delivered to this actor.
+    /// If it has already stopped the message is delivered right away.
+    /// Watching does not keep the other actor alive and ends when this actor stops.
+    pub fn watch<B: Actor>(&mut self, addr: &Addr<B>)
+    where
+        A: Handler<Terminated>,
+    {
+        let id = addr.context_id;
+        let running = addr.running.clone();
+        let myself = std::sync::Weak::clone(&self.weak_force_tx);
+
+        let handle = self.spawn_task(async move {
+            let reason = running.await.unwrap_or(StopReason::Canceled);
+            let Some(tx) = myself.upgrade() else {
+                return;
+            };
+            let terminated = Payload::task(move |actor: &mut A, ctx: &mut Context<A>| {
+                ctx.watching.remove(&id);
+                Box::pin(Handler::handle(actor, ctx, Terminated { id, reason }))
+            });
+            if tx.send(terminated).is_err() {
+                log::trace!("{} stopped before {id} terminated", A::NAME);
+            }
+        });
+
+        if let Some(previous) = self.watching.insert(id, handle) {
+            previous.abort();
+        }
+    }
+
+    /// Stop watching another actor.
+    ///
+    /// No [`Terminated`] message will be delivered for it, unless it is already queued.
+    pub fn unwatch<B: Actor>(&mut self, addr: &Addr<B>) {
+        if let Some(handle) = self.watching.remove(&addr.context_id) {
+            handle.abort();
+        }
+    }
+
+    /// Returns `true` if this actor is watching the actor with the given id.
+    pub fn is_watching(&self, id: ContextID) -> bool {
+        self.watching.contains_key(&id)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use crate::{DynResult, prelude::*};
+
+    use super::Terminated;
+
+    #[derive(Debug, Default)]
+    struct Worker;
+    impl Actor for Worker {}
+
+    #[derive(Default)]
+    struct Watcher {
+        worker: Option<Addr<Worker>>,
+        unwatch: bool,
+        terminated: Vec<Terminated>,
+    }
+
+    impl Actor for Watcher {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+            let worker = self.worker.as_ref().unwrap();
+            ctx.watch(worker);
+            assert!(ctx.is_watching(worker.id()));
+            if self.unwatch {
+                ctx.unwatch(worker);
+            }
+            Ok(())
+        }
+    }
+
+    impl Handler<Terminated> for Watcher {
+        async fn handle(&mut self, ctx: &mut Context<Self>, msg: Terminated) {
+            assert!(!ctx.is_watching(msg.id));
+            self.terminated.push(msg);
+        }
+    }
+
+    async fn watch(unwatch: bool) -> (crate::ContextID, Vec<Terminated>) {
+        let mut worker = Worker.spawn();
+        let id = worker.id();
+        let watcher = Watcher {
+            worker: Some(worker.clone()),
+            unwatch,
+            ..Default::default()
+        }
+        .spawn_owning();
+
+        worker.stop().unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (watcher, _) = watcher.stop_and_join().unwrap().await;
+        (id, watcher.unwrap().terminated)
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn delivers_terminated() {
+        let (id, terminated) = watch(false).await;
+        assert_eq!(
+            terminated,
+            vec![Terminated {
+                id,
+                reason: StopReason::Stopped
+            }]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn unwatch_suppresses_terminated() {
+        let (_, terminated) = watch(true).await;
+        assert!(terminated.is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn reports_already_stopped_actor() {
+        let worker = Worker.spawn();
+        let id = worker.id();
+        worker.clone().stop_and_join().await.unwrap();
+
+        let watcher = Watcher {
+            worker: Some(worker),
+            ..Default::default()
+        }
+        .spawn_owning();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let (watcher, _) = watcher.stop_and_join().unwrap().await;
+        let ids: Vec<_> = watcher.unwrap().terminated.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![id]);
+    }
+}
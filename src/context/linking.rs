@@ -0,0 +1,284 @@
+//! Bidirectional links between actors.
+//!
+//! Linked actors fail together: if one of them stops with a failure, the other one is stopped as well,
+//! unless it [traps exits](`Context::trap_exit`) and handles an [`Exit`] message instead.
+use crate::{Context, Handler, Message, StopReason, actor::Actor, environment::TaskFuture};
+
+use super::ContextID;
+
+/// Delivered to an actor that [traps exits](`Context::trap_exit`) when a [linked](`Context::link`) actor stops.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exit {
+    /// The id of the linked actor, see [`Addr::id`](`crate::Addr::id`).
+    pub id: ContextID,
+    /// Why the linked actor stopped.
+    pub reason: StopReason,
+}
+
+impl Message for Exit {
+    type Response = ();
+}
+
+pub(crate) type ExitHandler<A> = for<'a> fn(&'a mut A, &'a mut Context<A>, Exit) -> TaskFuture<'a>;
+
+fn handle_exit<'a, A: Handler<Exit>>(
+    actor: &'a mut A,
+    ctx: &'a mut Context<A>,
+    exit: Exit,
+) -> TaskFuture<'a> {
+    Box::pin(Handler::handle(actor, ctx, exit))
+}
+
+/// Linking
+impl<A: Actor> Context<A> {
+    /// Receive an [`Exit`] message instead of stopping when a linked actor stops.
+    ///
+    /// Trapping actors are notified about every linked actor that stops, not only about failures.
+    pub fn trap_exit(&mut self, trap: bool)
+    where
+        A: Handler<Exit>,
+    {
+        let handler: ExitHandler<A> = handle_exit::<A>;
+        self.trap_exit = trap.then_some(handler);
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "custom_runtime"))]
+mod spawning {
+    use crate::{
+        Addr, Context,
+        actor::Actor,
+        context::RunningFuture,
+        environment::{Payload, TaskFuture},
+    };
+
+    use super::{ContextID, Exit, StopReason};
+
+    /// Linking
+    impl<A: Actor> Context<A> {
+        /// Link this actor to another one.
+        ///
+        /// Links are bidirectional: when either actor stops with a [failure](`StopReason::is_failure`),
+        /// the other one stops with [`StopReason::LinkFailed`], which in turn propagates to its own links.
+        /// Actors that [trap exits](`Context::trap_exit`) receive an [`Exit`] message instead.
+        pub fn link<B: Actor>(&mut self, addr: &Addr<B>) -> crate::error::Result<()> {
+            let (id, running) = (self.id, self.running.clone());
+            addr.payload_force_tx
+                .send(Payload::task(move |_, ctx: &mut Context<B>| {
+                    ctx.monitor_link(id, running);
+                    Box::pin(async {})
+                }))?;
+            self.monitor_link(addr.context_id, addr.running.clone());
+            Ok(())
+        }
+
+        /// Remove the link to another actor, in both directions.
+        pub fn unlink<B: Actor>(&mut self, addr: &Addr<B>) {
+            if let Some(handle) = self.links.remove(&addr.context_id) {
+                handle.abort();
+            }
+            let id = self.id;
+            let unlink = Payload::task(move |_, ctx: &mut Context<B>| {
+                if let Some(handle) = ctx.links.remove(&id) {
+                    handle.abort();
+                }
+                Box::pin(async {})
+            });
+            if addr.payload_force_tx.send(unlink).is_err() {
+                log::trace!("{} already stopped", addr.context_id);
+            }
+        }
+
+        /// Returns `true` if this actor is linked to the actor with the given id.
+        pub fn is_linked(&self, id: ContextID) -> bool {
+            self.links.contains_key(&id)
+        }
+
+        fn monitor_link(&mut self, id: ContextID, running: RunningFuture) {
+            let myself = std::sync::Weak::clone(&self.weak_force_tx);
+            let handle = self.spawn_task(async move {
+                let reason = running.await.unwrap_or(StopReason::Canceled);
+                let Some(tx) = myself.upgrade() else {
+                    return;
+                };
+                let exit = Payload::task(move |actor: &mut A, ctx: &mut Context<A>| {
+                    ctx.linked_actor_exited(actor, Exit { id, reason })
+                });
+                if tx.send(exit).is_err() {
+                    log::trace!("{} stopped before {id}", A::NAME);
+                }
+            });
+
+            if let Some(previous) = self.links.insert(id, handle) {
+                previous.abort();
+            }
+        }
+
+        fn linked_actor_exited<'a>(&'a mut self, actor: &'a mut A, exit: Exit) -> TaskFuture<'a> {
+            self.links.remove(&exit.id);
+            if let Some(handle_exit) = self.trap_exit {
+                return handle_exit(actor, self, exit);
+            }
+
+            if exit.reason.is_failure() {
+                log::warn!(
+                    "{} linked to {} which failed: {}",
+                    A::NAME,
+                    exit.id,
+                    exit.reason
+                );
+                let sent = self
+                    .weak_force_tx
+                    .upgrade()
+                    .map(|tx| tx.send(Payload::Exit(StopReason::LinkFailed(exit.id))));
+                if let Some(Err(_)) = sent {
+                    log::trace!("{} already stopping", A::NAME);
+                }
+            }
+            Box::pin(async {})
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use crate::{DynResult, prelude::*};
+
+    use super::Exit;
+
+    #[derive(Debug, Default)]
+    struct Worker;
+    impl Actor for Worker {}
+
+    struct Fail;
+    impl Message for Fail {
+        type Response = ();
+    }
+
+    impl Handler<Fail> for Worker {
+        async fn handle(&mut self, _: &mut Context<Self>, _: Fail) {
+            panic!("worker failed");
+        }
+    }
+
+    #[derive(Default)]
+    struct Partner {
+        worker: Option<Addr<Worker>>,
+        trap: bool,
+        unlink: bool,
+        exits: Vec<Exit>,
+    }
+
+    impl Actor for Partner {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+            ctx.trap_exit(self.trap);
+            let worker = self.worker.take().unwrap();
+            ctx.link(&worker)?;
+            assert!(ctx.is_linked(worker.id()));
+            if self.unlink {
+                ctx.unlink(&worker);
+            }
+            Ok(())
+        }
+    }
+
+    impl Handler<Fail> for Partner {
+        async fn handle(&mut self, _: &mut Context<Self>, _: Fail) {
+            panic!("partner failed");
+        }
+    }
+
+    impl Handler<Exit> for Partner {
+        async fn handle(&mut self, _: &mut Context<Self>, msg: Exit) {
+            self.exits.push(msg);
+        }
+    }
+
+    async fn link(partner: Partner, fail: bool) -> (crate::ContextID, Addr<Worker>, Partner) {
+        let mut worker = Worker.spawn();
+        let id = worker.id();
+        let partner = Partner {
+            worker: Some(worker.clone()),
+            ..partner
+        }
+        .spawn_owning();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        if fail {
+            worker.send(Fail).await.unwrap();
+        } else {
+            worker.stop().unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let (partner, _) = partner.stop_and_join().unwrap().await;
+        (id, worker, partner.unwrap())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn failure_stops_linked_actor() {
+        let worker = Worker.spawn();
+        let id = worker.id();
+        let partner = Partner {
+            worker: Some(worker.clone()),
+            ..Default::default()
+        }
+        .spawn();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        worker.send(Fail).await.unwrap();
+        assert_eq!(partner.await, StopReason::LinkFailed(id));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn failure_propagates_both_ways() {
+        let worker = Worker.spawn();
+        let partner = Partner {
+            worker: Some(worker.clone()),
+            ..Default::default()
+        }
+        .spawn();
+        let id = partner.id();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        partner.send(Fail).await.unwrap();
+        assert_eq!(worker.await, StopReason::LinkFailed(id));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn normal_stop_does_not_propagate() {
+        let (_, _, partner) = link(Partner::default(), false).await;
+        assert!(partner.exits.is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn trapped_exit_is_delivered() {
+        let partner = Partner {
+            trap: true,
+            ..Default::default()
+        };
+        let (id, _, partner) = link(partner, true).await;
+        assert_eq!(partner.exits.len(), 1);
+        assert!(
+            partner
+                .exits
+                .iter()
+                .all(|exit| exit.id == id && exit.reason.is_failure())
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn unlinked_actors_fail_alone() {
+        let partner = Partner {
+            unlink: true,
+            ..Default::default()
+        };
+        let (_, worker, partner) = link(partner, true).await;
+        assert!(matches!(worker.await, StopReason::Panicked(_)));
+        assert!(partner.exits.is_empty());
+    }
+}
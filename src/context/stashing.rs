@@ -0,0 +1,339 @@
+//! Setting messages aside until the actor is ready for them.
+use std::{
+    any::{Any, TypeId},
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::{
+    Context, Message,
+    actor::Actor,
+    context::ContextID,
+    dead_letters::{self, DeadLetter, DeadLetterReason},
+    environment::Payload,
+    error::{ActorError, Result},
+};
+
+/// Replayed messages, the actor's event loop takes them before anything else in the mailbox.
+pub(crate) type Unstashed<A> = Arc<Mutex<VecDeque<Payload<A>>>>;
+
+pub(crate) struct Stash<A> {
+    stashed: VecDeque<Payload<A>>,
+    /// The type of the message that is currently being handled, only that one can be stashed.
+    handling: Option<TypeId>,
+    /// The message that is currently being handled, if the handler stashed it.
+    current: Option<Box<dyn Any + Send>>,
+    unstashed: Unstashed<A>,
+    pub(crate) capacity: usize,
+}
+
+impl<A> Default for Stash<A> {
+    fn default() -> Self {
+        Self {
+            stashed: VecDeque::new(),
+            handling: None,
+            current: None,
+            unstashed: Arc::default(),
+            capacity: Self::DEFAULT_CAPACITY,
+        }
+    }
+}
+
+impl<A> Stash<A> {
+    pub(crate) const DEFAULT_CAPACITY: usize = 1000;
+
+    pub(crate) fn unstashed(&self) -> Unstashed<A> {
+        Arc::clone(&self.unstashed)
+    }
+
+    /// Allow the handler of `M` to stash its message.
+    pub(crate) fn begin<M: Message>(&mut self) {
+        self.handling = Some(TypeId::of::<M>());
+        self.current = None;
+    }
+
+    /// Take the message that is being handled back if its handler stashed it.
+    pub(crate) fn take<M: Message>(&mut self) -> Option<M> {
+        self.handling = None;
+        self.current.take()?.downcast::<M>().ok().map(|msg| *msg)
+    }
+
+    pub(crate) fn push(&mut self, payload: Payload<A>) {
+        self.stashed.push_back(payload);
+    }
+
+    /// Drop the stashed messages, those that were unstashed but not handled anymore and `pending` ones.
+    ///
+    /// They are reported as [`DeadLetter`]s, like the messages left in the mailbox.
+    /// If the actor `panicked`, their callers are told so.
+    pub(crate) fn report_stopped(
+        &mut self,
+        target: ContextID,
+        actor: &'static str,
+        pending: Vec<Payload<A>>,
+        panicked: Option<&str>,
+    ) {
+        let unstashed = std::mem::take(
+            &mut *self
+                .unstashed
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        for payload in unstashed
+            .into_iter()
+            .chain(self.stashed.drain(..))
+            .chain(pending)
+        {
+            if let Some(message) = panicked {
+                payload.mark_panicked(message);
+            }
+            if let Some(message) = payload.message_name() {
+                dead_letters::report(DeadLetter {
+                    target,
+                    actor,
+                    message,
+                    reason: DeadLetterReason::ActorStopped,
+                });
+            }
+        }
+    }
+}
+
+/// Stashing
+impl<A: Actor> Context<A> {
+    /// Set the message that is currently being handled aside, until [`unstash_all`](`Context::unstash_all`) is called.
+    ///
+    /// Call this from within the message's own handler, with the message that was passed in.
+    /// If the message was sent via [`call`](`crate::Addr::call`),
+    /// the caller keeps waiting and the response of this handler invocation is discarded.
+    ///
+    /// Fails with [`ActorError::StashFull`] once the stash holds as many messages as its capacity,
+    /// see [`stash_capacity`](`crate::build`).
+    /// Fails with [`ActorError::NotStashable`] outside of a message handler,
+    /// for a message of another type than the one being handled, or if the message was stashed already.
+    /// The message is then handled as if it was not stashed.
+    ///
+    /// Messages that are still stashed when the actor stops are reported as [`DeadLetter`]s.
+    ///
+    /// ```no_run
+    /// # use hannibal::prelude::*;
+    /// # #[derive(Debug, Clone, Message)]
+    /// # struct Query;
+    /// # #[derive(Debug, Message)]
+    /// # struct Connected;
+    /// #[derive(Default)]
+    /// struct Client {
+    ///     connected: bool,
+    /// }
+    /// # impl Actor for Client {}
+    ///
+    /// impl Handler<Query> for Client {
+    ///     async fn handle(&mut self, ctx: &mut Context<Self>, query: Query) {
+    ///         if !self.connected {
+    ///             ctx.stash(query).unwrap();
+    ///             return;
+    ///         }
+    ///         // handle query
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Connected> for Client {
+    ///     async fn handle(&mut self, ctx: &mut Context<Self>, _: Connected) {
+    ///         self.connected = true;
+    ///         ctx.unstash_all();
+    ///     }
+    /// }
+    /// ```
+    pub fn stash<M: Message>(&mut self, msg: M) -> Result<()> {
+        if self.stash.handling != Some(TypeId::of::<M>()) || self.stash.current.is_some() {
+            return Err(ActorError::NotStashable);
+        }
+        if self.stash.stashed.len() >= self.stash.capacity {
+            return Err(ActorError::StashFull);
+        }
+        self.stash.current = Some(Box::new(msg));
+        Ok(())
+    }
+
+    /// Handle all stashed messages next, in the order they were stashed.
+    ///
+    /// They are handled before any other message in the mailbox.
+    pub fn unstash_all(&mut self) {
+        let mut unstashed = self
+            .stash
+            .unstashed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for payload in self.stash.stashed.drain(..).rev() {
+            unstashed.push_front(payload);
+        }
+    }
+
+    /// How many messages are currently stashed.
+    pub fn stash_len(&self) -> usize {
+        self.stash.stashed.len()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use crate::{error::ActorError, prelude::*};
+
+    #[derive(Debug, Default)]
+    struct Client {
+        connected: bool,
+        handled: Vec<u32>,
+        errors: Vec<ActorError>,
+    }
+    impl Actor for Client {}
+
+    struct Query(u32);
+    impl Message for Query {
+        type Response = u32;
+    }
+
+    struct Notify(u32);
+    impl Message for Notify {
+        type Response = ();
+    }
+
+    struct Connect;
+    impl Message for Connect {
+        type Response = ();
+    }
+
+    impl Handler<Query> for Client {
+        async fn handle(&mut self, ctx: &mut Context<Self>, Query(n): Query) -> u32 {
+            if !self.connected {
+                ctx.stash(Query(n)).unwrap();
+                return 0;
+            }
+            self.handled.push(n);
+            n * 10
+        }
+    }
+
+    impl Handler<Notify> for Client {
+        async fn handle(&mut self, ctx: &mut Context<Self>, Notify(n): Notify) {
+            if !self.connected {
+                if let Err(error) = ctx.stash(Notify(n)) {
+                    assert_eq!(error, ActorError::StashFull);
+                    self.handled.push(n + 100);
+                }
+                return;
+            }
+            self.handled.push(n);
+        }
+    }
+
+    struct Misuse;
+    impl Message for Misuse {
+        type Response = ();
+    }
+
+    impl Handler<Misuse> for Client {
+        async fn handle(&mut self, ctx: &mut Context<Self>, msg: Misuse) {
+            self.errors.extend(ctx.stash(Notify(0)).err());
+            if !self.connected {
+                ctx.stash(msg).unwrap();
+                self.errors.extend(ctx.stash(Misuse).err());
+            }
+        }
+    }
+
+    struct Early;
+    impl Actor for Early {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult<()> {
+            ctx.stash(Connect)?;
+            Ok(())
+        }
+    }
+
+    impl Handler<Connect> for Client {
+        async fn handle(&mut self, ctx: &mut Context<Self>, _: Connect) {
+            self.connected = true;
+            ctx.unstash_all();
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn replays_stashed_messages_in_order() {
+        let addr = crate::build(Client::default()).unbounded().spawn_owning();
+
+        addr.send(Notify(1)).await.unwrap();
+        addr.send(Notify(2)).await.unwrap();
+        addr.send(Connect).await.unwrap();
+        addr.send(Notify(3)).await.unwrap();
+
+        let (client, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(client.unwrap().handled, vec![1, 2, 3]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn stashed_calls_are_answered_after_unstashing() {
+        let addr = Client::default().spawn_owning();
+        let client = addr.to_addr();
+
+        let query = tokio::spawn(async move { client.call(Query(4)).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(
+            !query.is_finished(),
+            "stashed call must not be answered yet"
+        );
+
+        addr.send(Connect).await.unwrap();
+        assert_eq!(query.await.unwrap().unwrap(), 40);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn cancelled_stashed_calls_are_not_handled() {
+        let addr = Client::default().spawn_owning();
+
+        let query = addr.call_timeout(Query(5), Duration::from_millis(20)).await;
+        assert_eq!(query, Err(ActorError::CallTimeout));
+        addr.send(Connect).await.unwrap();
+
+        let (client, _) = addr.stop_and_join().unwrap().await;
+        assert!(client.unwrap().handled.is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn stash_is_bounded() {
+        let addr = crate::build(Client::default())
+            .unbounded()
+            .stash_capacity(1)
+            .spawn_owning();
+
+        addr.send(Notify(1)).await.unwrap();
+        addr.send(Notify(2)).await.unwrap();
+        addr.send(Connect).await.unwrap();
+
+        let (client, _) = addr.stop_and_join().unwrap().await;
+        assert_eq!(client.unwrap().handled, vec![102, 1]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn only_stashes_the_message_being_handled() {
+        let addr = crate::build(Client::default()).unbounded().spawn_owning();
+        addr.send(Misuse).await.unwrap();
+        addr.send(Connect).await.unwrap();
+
+        let (client, _) = addr.stop_and_join().unwrap().await;
+        // another message and stashing twice, then another message again once it is unstashed
+        assert!(client.unwrap().errors.iter().eq(&[
+            ActorError::NotStashable,
+            ActorError::NotStashable,
+            ActorError::NotStashable
+        ]));
+
+        let early = Early.spawn();
+        assert_eq!(
+            early.await,
+            StopReason::StartFailed(ActorError::NotStashable.to_string())
+        );
+    }
+}
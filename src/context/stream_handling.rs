@@ -0,0 +1,219 @@
+//! Streams that are added to an actor at runtime.
+//!
+//! Unlike the stream an actor is spawned on, these streams don't own the actor:
+//! they can come and go while the actor keeps running.
+use futures::{Stream, StreamExt as _, future::AbortHandle};
+
+use crate::{
+    Context, StreamHandler,
+    actor::Actor,
+    environment::{Envelope, Payload},
+    error::ActorError,
+};
+
+/// Handle to a stream added via [`Context::add_stream`].
+///
+/// Dropping the handle leaves the stream attached, call [`StreamHandle::cancel`] to detach it.
+#[derive(Debug, Clone)]
+pub struct StreamHandle {
+    abort: AbortHandle,
+}
+
+impl StreamHandle {
+    /// Stop handling items from this stream.
+    ///
+    /// Items that were already forwarded to the actor are still handled,
+    /// [`StreamHandler::finished`] is not called for a canceled stream.
+    pub fn cancel(&self) {
+        self.abort.abort();
+    }
+
+    /// Returns `true` if the stream was canceled, either explicitly or because the actor stopped.
+    pub fn is_canceled(&self) -> bool {
+        self.abort.is_aborted()
+    }
+}
+
+/// Stream Handling
+impl<A: Actor> Context<A> {
+    /// Handle the items of an additional stream.
+    ///
+    /// Every item is passed to the matching [`StreamHandler`] implementation,
+    /// once the stream ends [`StreamHandler::finished`] is called.
+    /// The actor keeps running when the stream ends, you can add as many streams as you like.
+    /// Streams are dropped when the actor stops.
+    ///
+    /// Items are queued like messages: a bounded mailbox slows the stream down
+    /// or drops items according to its [`OverflowPolicy`](`crate::OverflowPolicy`).
+    pub fn add_stream<S>(&mut self, stream: S) -> StreamHandle
+    where
+        S: Stream + Send + 'static,
+        S::Item: Send + 'static,
+        A: StreamHandler<S::Item>,
+    {
+        let myself = std::sync::Weak::clone(&self.weak_tx);
+        let abort = self.spawn_task(async move {
+            let mut stream = std::pin::pin!(stream);
+            while let Some(item) = stream.next().await {
+                let Some(tx) = myself.upgrade() else {
+                    return;
+                };
+                let item =
+                    Payload::message(Envelope::item::<S::Item>(), move |actor: &mut A, ctx| {
+                        Box::pin(StreamHandler::handle(actor, ctx, item))
+                    });
+                match tx.send(item).await {
+                    // rejected items are reported as dead letters
+                    Ok(()) | Err(ActorError::MailboxFull) => {}
+                    Err(_) => return,
+                }
+            }
+
+            let Some(tx) = myself.upgrade() else {
+                return;
+            };
+            let finished = Payload::task(|actor: &mut A, ctx| {
+                Box::pin(StreamHandler::<S::Item>::finished(actor, ctx))
+            });
+            if tx.send(finished).await.is_err() {
+                log::warn!("{} stopped before stream finished", A::NAME);
+            }
+        });
+
+        StreamHandle { abort }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use futures::{StreamExt as _, stream};
+
+    use crate::{DynResult, prelude::*};
+
+    use super::StreamHandle;
+
+    #[derive(Debug, Default)]
+    struct Collector {
+        numbers: Vec<i32>,
+        words: Vec<&'static str>,
+        finished: usize,
+        ticks: Option<StreamHandle>,
+    }
+
+    impl Actor for Collector {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+            ctx.add_stream(stream::iter([1, 2, 3]));
+            ctx.add_stream(stream::iter(["fizz", "buzz"]));
+            self.ticks = Some(ctx.add_stream(stream::pending::<u8>()));
+            Ok(())
+        }
+    }
+
+    impl StreamHandler<i32> for Collector {
+        async fn handle(&mut self, _: &mut Context<Self>, msg: i32) {
+            self.numbers.push(msg);
+        }
+
+        async fn finished(&mut self, _: &mut Context<Self>) {
+            self.finished += 1;
+        }
+    }
+
+    impl StreamHandler<&'static str> for Collector {
+        async fn handle(&mut self, _: &mut Context<Self>, msg: &'static str) {
+            self.words.push(msg);
+        }
+
+        async fn finished(&mut self, _: &mut Context<Self>) {
+            self.finished += 1;
+        }
+    }
+
+    impl StreamHandler<u8> for Collector {
+        async fn handle(&mut self, _: &mut Context<Self>, _: u8) {}
+
+        async fn finished(&mut self, _: &mut Context<Self>) {
+            self.finished += 1;
+        }
+    }
+
+    struct CancelTicks;
+    impl Message for CancelTicks {
+        type Response = ();
+    }
+
+    impl Handler<CancelTicks> for Collector {
+        async fn handle(&mut self, _: &mut Context<Self>, _: CancelTicks) {
+            self.ticks.as_ref().unwrap().cancel();
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn handles_multiple_streams() {
+        let addr = Collector::default().spawn_owning();
+        addr.send(CancelTicks).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (collector, _) = addr.stop_and_join().unwrap().await;
+        let collector = collector.unwrap();
+        assert_eq!(collector.numbers, vec![1, 2, 3]);
+        assert_eq!(collector.words, vec!["fizz", "buzz"]);
+        assert_eq!(
+            collector.finished, 2,
+            "canceled stream must not be finished"
+        );
+        assert!(collector.ticks.unwrap().is_canceled());
+    }
+
+    #[derive(Debug, Default)]
+    struct Slow {
+        pulled: Arc<AtomicUsize>,
+        handled: usize,
+    }
+
+    impl Actor for Slow {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+            let pulled = Arc::clone(&self.pulled);
+            ctx.add_stream(stream::iter(0..20u32).inspect(move |_| {
+                pulled.fetch_add(1, Ordering::SeqCst);
+            }));
+            Ok(())
+        }
+    }
+
+    impl StreamHandler<u32> for Slow {
+        async fn handle(&mut self, _: &mut Context<Self>, _: u32) {
+            // one item is handled, two are queued and one waits for room
+            assert!(self.pulled.load(Ordering::SeqCst) <= self.handled + 4);
+            tokio::time::sleep(Duration::from_millis(2)).await;
+            self.handled += 1;
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn bounded_mailbox_slows_down_streams() {
+        let addr = crate::build(Slow::default()).bounded(2).spawn_owning();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(addr.consume().await.unwrap().handled, 20);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn streams_are_canceled_when_actor_stops() {
+        let (collector, _) = Collector::default()
+            .spawn_owning()
+            .stop_and_join()
+            .unwrap()
+            .await;
+        assert!(collector.unwrap().ticks.unwrap().is_canceled());
+    }
+}
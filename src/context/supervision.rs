@@ -0,0 +1,492 @@
+//! Supervision of child actors.
+//!
+//! Children created via [`Context::create_child`] are kept alive by their parent
+//! and restarted according to the parent's [`SupervisionStrategy`] when their event loop exits with an error.
+//! If the children fail more often than the parent's [`RestartIntensity`] allows,
+//! the parent gives up and stops with [`StopReason::TooManyRestarts`], escalating the failure to its own supervisor.
+use std::{marker::PhantomData, time::Duration};
+
+use futures::future::{BoxFuture, join_all};
+
+use crate::{
+    Addr, Context, DynResult, StopReason,
+    actor::{
+        Actor,
+        restart_strategy::{Backoff, RestartIntensity, RestartTracker},
+        spawner::Spawner,
+    },
+    channel::WeakForceChanTx,
+    environment::{Environment, Payload},
+    error::Result,
+};
+
+use super::ContextID;
+
+/// Decides which children are restarted when one of them fails.
+///
+/// This mirrors the strategies known from Erlang/OTP supervisors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SupervisionStrategy {
+    /// Only the failed child is restarted.
+    #[default]
+    OneForOne,
+    /// All children are stopped and restarted if one of them fails.
+    OneForAll,
+    /// The failed child and all children created after it are restarted.
+    RestForOne,
+}
+
+#[derive(Default)]
+pub(crate) struct Supervision {
+    pub(crate) strategy: SupervisionStrategy,
+    pub(crate) restarts: RestartTracker,
+    pub(crate) children: Vec<Box<dyn SupervisedChild>>,
+}
+
+pub(crate) trait SupervisedChild: Send {
+    fn id(&self) -> ContextID;
+    fn stop(&mut self) -> Result<()>;
+    /// Resolves once the current instance of the child stopped.
+    fn join(&self) -> BoxFuture<'static, StopReason>;
+    /// Wait via the sleep of the child's spawner.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+    fn respawn(&mut self);
+}
+
+/// Creates a fresh instance of a child along with its event loop.
+type CreateLoop<C> = Box<dyn FnMut() -> (BoxFuture<'static, DynResult<C>>, Addr<C>) + Send>;
+
+/// A child actor together with the factory that is used to recreate it.
+struct Supervised<P, C, S>
+where
+    C: Actor,
+{
+    create_loop: CreateLoop<C>,
+    addr: Addr<C>,
+    parent: WeakForceChanTx<P>,
+    spawner: PhantomData<S>,
+}
+
+impl<P, C, S> Supervised<P, C, S>
+where
+    P: Actor,
+    C: Actor,
+    S: Spawner<C>,
+{
+    fn spawn(create_loop: &mut CreateLoop<C>, parent: &WeakForceChanTx<P>) -> Addr<C> {
+        let (event_loop, addr) = create_loop();
+        let parent = WeakForceChanTx::clone(parent);
+        let child_id = addr.context_id;
+
+        S::spawn_actor(async move {
+            let result = event_loop.await;
+            if let Err(error) = &result {
+                log::warn!("child {} ({child_id}) failed: {error}", C::NAME);
+                if let Some(tx) = parent.upgrade() {
+                    let notification = Payload::task(move |_, ctx: &mut Context<P>| {
+                        Box::pin(ctx.handle_child_failure(child_id))
+                    });
+                    if tx.send(notification).is_err() {
+                        log::warn!("failed to notify parent of {child_id}");
+                    }
+                }
+            }
+            result
+        });
+
+        addr
+    }
+}
+
+impl<P, C, S> SupervisedChild for Supervised<P, C, S>
+where
+    P: Actor,
+    C: Actor,
+    S: Spawner<C> + Send + 'static,
+{
+    fn id(&self) -> ContextID {
+        self.addr.context_id
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.addr.stop()
+    }
+
+    fn join(&self) -> BoxFuture<'static, StopReason> {
+        Box::pin(self.addr.clone())
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(S::sleep(duration))
+    }
+
+    fn respawn(&mut self) {
+        self.addr = Self::spawn(&mut self.create_loop, &self.parent);
+    }
+}
+
+/// Supervision
+impl<A: Actor> Context<A> {
+    /// Set the strategy that is applied when a child actor fails.
+    ///
+    /// The default is [`SupervisionStrategy::OneForOne`].
+    pub const fn set_supervision_strategy(&mut self, strategy: SupervisionStrategy) {
+        self.supervision.strategy = strategy;
+    }
+
+    /// Give up once the children failed more than `max_restarts` times within `within`.
+    ///
+    /// The actor then stops with [`StopReason::TooManyRestarts`], by default it restarts its children indefinitely.
+    pub const fn set_supervision_intensity(&mut self, max_restarts: usize, within: Duration) {
+        self.supervision.restarts.set_intensity(RestartIntensity {
+            max_restarts,
+            within,
+        });
+    }
+
+    /// Wait before restarting failed children, see [`Backoff`].
+    pub const fn set_supervision_backoff(&mut self, backoff: Backoff) {
+        self.supervision.restarts.set_backoff(backoff);
+    }
+
+    /// Create a supervised child actor with an unbounded mailbox.
+    ///
+    /// The child is stopped when the parent actor is stopped.
+    /// If the child's event loop exits with an error, `create_child` is called again
+    /// to replace it according to the configured [`SupervisionStrategy`].
+    /// Use [`create_child_with`](`Context::create_child_with`) to configure the child.
+    pub fn create_child<F, C, S>(&mut self, mut create_child: F)
+    where
+        F: FnMut() -> C + Send + 'static,
+        C: Actor + crate::spawner::Spawnable<S>,
+        S: Spawner<C> + Send + 'static,
+    {
+        self.supervise::<C, S>(Box::new(move || {
+            let (event_loop, addr) = Environment::unbounded().create_loop(create_child());
+            (Box::pin(event_loop), addr)
+        }));
+    }
+
+    /// Create a supervised child actor from a [builder](`crate::build`).
+    ///
+    /// Like [`create_child`](`Context::create_child`), but every instance of the child
+    /// is configured and spawned by the builder that `build_child` returns.
+    ///
+    /// ```no_run
+    /// # use hannibal::prelude::*;
+    /// # #[derive(Default)]
+    /// # struct Worker;
+    /// # impl Actor for Worker {}
+    /// struct Supervisor;
+    ///
+    /// impl Actor for Supervisor {
+    ///     async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+    ///         ctx.create_child_with(|| hannibal::build(Worker).bounded(16));
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn create_child_with<F, C, S, R>(&mut self, mut build_child: F)
+    where
+        F: FnMut() -> crate::actor::builder::ActorBuilderWithChannel<C, S, R> + Send + 'static,
+        C: Actor,
+        S: Spawner<C> + Send + 'static,
+        R: crate::actor::restart_strategy::RestartStrategy<C> + 'static,
+    {
+        self.supervise::<C, S>(Box::new(move || build_child().create_loop()));
+    }
+
+    fn supervise<C, S>(&mut self, mut create_loop: CreateLoop<C>)
+    where
+        C: Actor,
+        S: Spawner<C> + Send + 'static,
+    {
+        let addr = Supervised::<A, C, S>::spawn(&mut create_loop, &self.weak_force_tx);
+        self.supervision
+            .children
+            .push(Box::new(Supervised::<A, C, S> {
+                create_loop,
+                addr,
+                parent: WeakForceChanTx::clone(&self.weak_force_tx),
+                spawner: PhantomData,
+            }));
+    }
+
+    async fn handle_child_failure(&mut self, failed: ContextID) {
+        let strategy = self.supervision.strategy;
+        let Some(position) = self
+            .supervision
+            .children
+            .iter()
+            .position(|child| child.id() == failed)
+        else {
+            log::trace!("ignoring failure of unknown child {failed}");
+            return;
+        };
+
+        let delay = match self
+            .supervision
+            .restarts
+            .register(std::time::Instant::now())
+        {
+            Ok(delay) => delay,
+            Err(error) => {
+                log::warn!("{} gives up on its children: {error}", A::NAME);
+                if let Some(tx) = self.weak_force_tx.upgrade() {
+                    let _ = tx.send(Payload::Exit(StopReason::TooManyRestarts));
+                }
+                return;
+            }
+        };
+
+        let affected = |index: usize| match strategy {
+            SupervisionStrategy::OneForOne => index == position,
+            SupervisionStrategy::OneForAll => true,
+            SupervisionStrategy::RestForOne => index >= position,
+        };
+
+        // old and new instances of a child never run side by side
+        let mut stopped = Vec::new();
+        for (_, child) in self
+            .supervision
+            .children
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| affected(*index))
+        {
+            if child.id() != failed && child.stop().is_err() {
+                log::trace!("child {} already stopped", child.id());
+            }
+            stopped.push(child.join());
+        }
+        join_all(stopped).await;
+
+        if !delay.is_zero() {
+            let backoff = self
+                .supervision
+                .children
+                .get(position)
+                .map(|child| child.sleep(delay));
+            if let Some(backoff) = backoff {
+                backoff.await;
+            }
+        }
+
+        for (_, child) in self
+            .supervision
+            .children
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| affected(*index))
+        {
+            log::debug!("restarting child {}", child.id());
+            child.respawn();
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use crate::{DynResult, prelude::*};
+
+    use super::SupervisionStrategy;
+
+    /// Fails to start until it has been started `failures` times.
+    struct Flaky {
+        starts: Arc<AtomicUsize>,
+        failures: usize,
+    }
+
+    impl Actor for Flaky {
+        async fn started(&mut self, _: &mut Context<Self>) -> DynResult {
+            if self.starts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err(String::from("not yet").into())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    struct Parent {
+        strategy: SupervisionStrategy,
+        first: (Arc<AtomicUsize>, usize),
+        second: (Arc<AtomicUsize>, usize),
+        third: (Arc<AtomicUsize>, usize),
+    }
+
+    impl Actor for Parent {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+            ctx.set_supervision_strategy(self.strategy);
+            for (starts, failures) in [&self.first, &self.second] {
+                let (starts, failures) = (Arc::clone(starts), *failures);
+                ctx.create_child(move || Flaky {
+                    starts: Arc::clone(&starts),
+                    failures,
+                });
+            }
+            let (starts, failures) = (Arc::clone(&self.third.0), self.third.1);
+            ctx.create_child_with(move || {
+                crate::build(Flaky {
+                    starts: Arc::clone(&starts),
+                    failures,
+                })
+                .bounded(1)
+            });
+            Ok(())
+        }
+    }
+
+    async fn supervise(strategy: SupervisionStrategy) -> [usize; 3] {
+        let counters: [Arc<AtomicUsize>; 3] = Default::default();
+        let addr = Parent {
+            strategy,
+            first: (Arc::clone(&counters[0]), 0),
+            second: (Arc::clone(&counters[1]), 1),
+            third: (Arc::clone(&counters[2]), 0),
+        }
+        .spawn();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        addr.stop_and_join().await.unwrap();
+        counters.map(|starts| starts.load(Ordering::SeqCst))
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn one_for_one() {
+        assert_eq!(supervise(SupervisionStrategy::OneForOne).await, [1, 2, 1]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn one_for_all() {
+        assert_eq!(supervise(SupervisionStrategy::OneForAll).await, [2, 2, 2]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn rest_for_one() {
+        assert_eq!(supervise(SupervisionStrategy::RestForOne).await, [1, 2, 2]);
+    }
+
+    struct Escalating(Arc<AtomicUsize>);
+
+    impl Actor for Escalating {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+            ctx.set_supervision_intensity(2, Duration::from_secs(10));
+            let starts = Arc::clone(&self.0);
+            ctx.create_child(move || Flaky {
+                starts: Arc::clone(&starts),
+                failures: usize::MAX,
+            });
+            Ok(())
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn escalates_when_children_fail_too_often() {
+        let starts = Arc::new(AtomicUsize::new(0));
+        let addr = Escalating(Arc::clone(&starts)).spawn();
+
+        let reason = tokio::time::timeout(Duration::from_secs(1), addr)
+            .await
+            .unwrap();
+        assert_eq!(reason, StopReason::TooManyRestarts);
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+    }
+
+    /// Takes a while to stop and notices if another instance is running at the same time.
+    struct Sibling {
+        alive: Arc<AtomicUsize>,
+        overlapped: Arc<AtomicBool>,
+    }
+
+    impl Actor for Sibling {
+        async fn started(&mut self, _: &mut Context<Self>) -> DynResult {
+            if self.alive.fetch_add(1, Ordering::SeqCst) > 0 {
+                self.overlapped.store(true, Ordering::SeqCst);
+            }
+            Ok(())
+        }
+
+        async fn stopped(&mut self, _: &mut Context<Self>, _: StopReason) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.alive.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    struct Siblings {
+        alive: Arc<AtomicUsize>,
+        overlapped: Arc<AtomicBool>,
+        starts: Arc<AtomicUsize>,
+    }
+
+    impl Actor for Siblings {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+            ctx.set_supervision_strategy(SupervisionStrategy::OneForAll);
+            let (alive, overlapped) = (Arc::clone(&self.alive), Arc::clone(&self.overlapped));
+            ctx.create_child(move || Sibling {
+                alive: Arc::clone(&alive),
+                overlapped: Arc::clone(&overlapped),
+            });
+            let starts = Arc::clone(&self.starts);
+            ctx.create_child(move || Flaky {
+                starts: Arc::clone(&starts),
+                failures: 1,
+            });
+            Ok(())
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn old_instances_stop_before_respawning() {
+        let alive = Arc::new(AtomicUsize::new(0));
+        let overlapped = Arc::new(AtomicBool::new(false));
+        let addr = Siblings {
+            alive: Arc::clone(&alive),
+            overlapped: Arc::clone(&overlapped),
+            starts: Arc::default(),
+        }
+        .spawn();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(alive.load(Ordering::SeqCst), 1);
+        assert!(!overlapped.load(Ordering::SeqCst));
+        addr.stop_and_join().await.unwrap();
+    }
+
+    struct Patient(Arc<AtomicUsize>);
+
+    impl Actor for Patient {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult {
+            ctx.set_supervision_backoff(crate::Backoff::Fixed(Duration::from_secs(3600)));
+            let starts = Arc::clone(&self.0);
+            ctx.create_child(move || Flaky {
+                starts: Arc::clone(&starts),
+                failures: 1,
+            });
+            Ok(())
+        }
+    }
+
+    // the backoff goes through the spawner, so it follows tokio's paused clock
+    #[test_log::test(tokio::test(start_paused = true))]
+    async fn backoff_uses_the_spawners_sleep() {
+        let starts = Arc::new(AtomicUsize::new(0));
+        let addr = Patient(Arc::clone(&starts)).spawn();
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+        addr.stop_and_join().await.unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+//! Where messages end up that could not be delivered.
+//!
+//! Mailboxes, senders and behaviors report every message that is dropped or cannot be delivered as a [`DeadLetter`].
+//! While the [`DeadLetters`] service is running it keeps the most recent ones
+//! and publishes them to everyone who [subscribed](`DeadLetters::subscribe`),
+//! otherwise they are only logged.
+use crate::{Message, context::ContextID, error::ActorError};
+
+/// Why a message was not handled, see [`DeadLetter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeadLetterReason {
+    /// The actor stopped before it handled the message, or was not running anymore when it was sent.
+    ActorStopped,
+    /// The bounded mailbox of the actor was full and the message was rejected.
+    MailboxFull,
+    /// The [`OverflowPolicy`](`crate::OverflowPolicy`) of the actor's mailbox dropped the message.
+    Dropped,
+    /// A newer message with the same [`ConflationKey`](`crate::ConflationKey`) replaced the message.
+    Conflated,
+    /// The actor did not get to the message before its [deadline](`crate::Message::DEADLINE`).
+    Expired,
+    /// The active [`Behavior`](`crate::Behavior`) discarded the message, see [`Unhandled::DeadLetters`](`crate::Unhandled::DeadLetters`).
+    Unhandled,
+}
+
+impl DeadLetterReason {
+    /// The reason for a message that a mailbox refused with `error`.
+    pub(crate) const fn from_error(error: &ActorError) -> Self {
+        match error {
+            ActorError::MailboxFull => Self::MailboxFull,
+            _ => Self::ActorStopped,
+        }
+    }
+}
+
+/// A message that was dropped or could not be delivered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeadLetter {
+    /// The context of the actor the message was sent to.
+    pub target: ContextID,
+    /// The [`NAME`](`crate::Actor::NAME`) of the actor the message was sent to.
+    pub actor: &'static str,
+    /// The type name of the message.
+    pub message: &'static str,
+    pub reason: DeadLetterReason,
+}
+
+impl Message for DeadLetter {
+    type Response = ();
+}
+
+/// Hand a dead letter to the [`DeadLetters`] service if it is running.
+pub(crate) fn report(letter: DeadLetter) {
+    // dead letters that can't be delivered are not reported again, that would never end
+    if letter.message == std::any::type_name::<DeadLetter>() {
+        return;
+    }
+    log::debug!(
+        "dead letter: {} to {} ({}): {:?}",
+        letter.message,
+        letter.actor,
+        letter.target,
+        letter.reason
+    );
+
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    if let Some(office) = <service::DeadLetters as crate::Service>::try_from_registry() {
+        if let Err(error) = office.force_send(letter) {
+            log::warn!("failed to report dead letter: {error}");
+        }
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub use service::DeadLetters;
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+mod service {
+    use std::collections::VecDeque;
+
+    use super::DeadLetter;
+    use crate::{
+        Actor, Addr, Broker, Context, Handler, Message, Service, WeakSender, error::Result,
+    };
+
+    /// The service that collects [`DeadLetter`]s.
+    ///
+    /// Dead letters are only collected while the service is running,
+    /// so start it via [`Service::setup`] or [`DeadLetters::subscribe`] before you rely on them.
+    /// It keeps the most recent ones, which comes in handy in tests.
+    ///
+    /// ```
+    /// # use hannibal::{DeadLetter, DeadLetterReason, DeadLetters, prelude::*};
+    /// # #[derive(Message)]
+    /// # struct Greet;
+    /// #[derive(Default)]
+    /// struct Greeter;
+    /// impl Actor for Greeter {}
+    /// impl Handler<Greet> for Greeter {
+    ///     async fn handle(&mut self, _: &mut Context<Self>, _: Greet) {}
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// DeadLetters::setup().await.unwrap();
+    ///
+    /// let addr = Greeter.spawn();
+    /// addr.clone().stop_and_join().await.unwrap();
+    /// assert!(addr.send(Greet).await.is_err());
+    ///
+    /// let recent = DeadLetters::from_registry().await.recent().await.unwrap();
+    /// assert!(recent.iter().any(|letter| letter.target == addr.id()
+    ///     && letter.reason == DeadLetterReason::ActorStopped));
+    /// # }
+    /// ```
+    pub struct DeadLetters {
+        recent: VecDeque<DeadLetter>,
+    }
+
+    impl DeadLetters {
+        /// How many dead letters are kept.
+        pub const RECENT_CAPACITY: usize = 1000;
+
+        /// Receive every dead letter from now on, starts the service if it is not running yet.
+        pub async fn subscribe(sender: WeakSender<DeadLetter>) -> Result<()> {
+            Self::from_registry().await;
+            Broker::subscribe(sender).await
+        }
+    }
+
+    impl Default for DeadLetters {
+        fn default() -> Self {
+            Self {
+                recent: VecDeque::with_capacity(Self::RECENT_CAPACITY),
+            }
+        }
+    }
+
+    impl Actor for DeadLetters {
+        const NAME: &'static str = "hannibal::DeadLetters";
+    }
+
+    impl Service for DeadLetters {}
+
+    impl Handler<DeadLetter> for DeadLetters {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, letter: DeadLetter) {
+            if self.recent.len() >= Self::RECENT_CAPACITY {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(letter);
+
+            if let Some(Err(error)) = Broker::try_publish(letter).await {
+                log::warn!("failed to publish dead letter: {error}");
+            }
+        }
+    }
+
+    struct Recent;
+
+    impl Message for Recent {
+        type Response = Vec<DeadLetter>;
+    }
+
+    impl Handler<Recent> for DeadLetters {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, _: Recent) -> Vec<DeadLetter> {
+            self.recent.iter().cloned().collect()
+        }
+    }
+
+    struct Clear;
+
+    impl Message for Clear {
+        type Response = ();
+    }
+
+    impl Handler<Clear> for DeadLetters {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, _: Clear) {
+            self.recent.clear();
+        }
+    }
+
+    impl Addr<DeadLetters> {
+        /// The most recent dead letters, oldest first.
+        pub async fn recent(&self) -> Result<Vec<DeadLetter>> {
+            self.call(Recent).await
+        }
+
+        /// Forget the recent dead letters.
+        pub async fn clear(&self) -> Result<()> {
+            self.send(Clear).await
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::{DeadLetter, DeadLetterReason, DeadLetters};
+    use crate::{
+        Behavior, BehaviorHandler, Broker, ContextID, Handles, OverflowPolicy, Unhandled,
+        error::ActorError, prelude::*,
+    };
+
+    #[derive(Debug, Default)]
+    struct Collector(Vec<DeadLetter>);
+
+    impl Actor for Collector {
+        async fn started(&mut self, ctx: &mut Context<Self>) -> DynResult<()> {
+            DeadLetters::subscribe(ctx.weak_sender()).await?;
+            Ok(())
+        }
+    }
+
+    impl Handler<DeadLetter> for Collector {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, letter: DeadLetter) {
+            self.0.push(letter);
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Worker;
+
+    impl Actor for Worker {
+        const NAME: &'static str = "worker";
+    }
+
+    struct Work;
+    impl Message for Work {
+        type Response = ();
+    }
+
+    impl Handler<Work> for Worker {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, _: Work) {}
+    }
+
+    struct Paused;
+
+    impl Behavior<Worker> for Paused {
+        const UNHANDLED: Unhandled = Unhandled::DeadLetters;
+        fn handles(_: &mut Handles<Worker, Self>) {}
+    }
+
+    struct Pause;
+    impl Message for Pause {
+        type Response = ();
+    }
+
+    impl Handler<Pause> for Worker {
+        async fn handle(&mut self, ctx: &mut Context<Self>, _: Pause) {
+            ctx.become_behavior(Paused);
+        }
+    }
+
+    struct Holding;
+
+    impl Behavior<Worker> for Holding {
+        fn handles(handles: &mut Handles<Worker, Self>) {
+            handles.on::<Work>();
+        }
+    }
+
+    impl BehaviorHandler<Worker, Work> for Holding {
+        async fn handle(&mut self, _: &mut Worker, ctx: &mut Context<Worker>, work: Work) {
+            ctx.stash(work).unwrap();
+        }
+    }
+
+    struct Hold;
+    impl Message for Hold {
+        type Response = ();
+    }
+
+    impl Handler<Hold> for Worker {
+        async fn handle(&mut self, ctx: &mut Context<Self>, _: Hold) {
+            ctx.become_behavior(Holding);
+        }
+    }
+
+    fn reasons(letters: &[DeadLetter], target: ContextID) -> Vec<DeadLetterReason> {
+        letters
+            .iter()
+            .filter(|letter| letter.target == target)
+            .inspect(|letter| {
+                assert_eq!(letter.actor, "worker");
+                assert_eq!(letter.message, std::any::type_name::<Work>());
+            })
+            .map(|letter| letter.reason)
+            .collect()
+    }
+
+    // the service is global, so everything is checked in one test
+    #[test_log::test(tokio::test)]
+    async fn reports_undeliverable_messages() {
+        let collector = Collector::default().spawn_owning();
+        collector.ping().await.unwrap();
+
+        let stopped = Worker.spawn();
+        stopped.clone().stop_and_join().await.unwrap();
+        assert_eq!(stopped.send(Work).await, Err(ActorError::AlreadyStopped));
+
+        let overflowing = crate::build(Worker)
+            .bounded(1)
+            .overflow(OverflowPolicy::DropNewest)
+            .spawn();
+        for _ in 0..3 {
+            overflowing.send(Work).await.unwrap();
+        }
+
+        let paused = Worker.spawn();
+        paused.send(Pause).await.unwrap();
+        paused.send(Work).await.unwrap();
+        paused.ping().await.unwrap();
+
+        let holding = Worker.spawn();
+        holding.send(Hold).await.unwrap();
+        holding.send(Work).await.unwrap();
+        holding.clone().stop_and_join().await.unwrap();
+
+        let office = DeadLetters::from_registry().await;
+        let recent = office.recent().await.unwrap();
+        assert_eq!(
+            reasons(&recent, stopped.id()),
+            vec![DeadLetterReason::ActorStopped]
+        );
+        assert_eq!(
+            reasons(&recent, overflowing.id()),
+            vec![DeadLetterReason::Dropped, DeadLetterReason::Dropped]
+        );
+        assert_eq!(
+            reasons(&recent, paused.id()),
+            vec![DeadLetterReason::Unhandled]
+        );
+        assert_eq!(
+            reasons(&recent, holding.id()),
+            vec![DeadLetterReason::ActorStopped]
+        );
+
+        Broker::<DeadLetter>::from_registry()
+            .await
+            .ping()
+            .await
+            .unwrap();
+        let Collector(collected) = collector.stop_and_join().unwrap().await.0.unwrap();
+        for id in [stopped.id(), overflowing.id(), paused.id(), holding.id()] {
+            assert_eq!(reasons(&collected, id), reasons(&recent, id));
+        }
+    }
+}
@@ -1,25 +1,65 @@
-use std::{future::Future, marker::PhantomData, time::Duration};
+use std::{
+    any::Any,
+    future::Future,
+    marker::PhantomData,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    task::Poll,
+    time::{Duration, Instant},
+};
 
 use futures::{FutureExt as _, Stream, StreamExt as _, channel::oneshot};
 
 use crate::{
     Addr, Context,
     actor::{
-        Actor,
-        restart_strategy::{RecreateFromDefault, RestartOnly, RestartStrategy},
+        Actor, StopReason,
+        restart_strategy::{
+            Backoff, RecreateFromDefault, RestartIntensity, RestartOnly, RestartStrategy,
+            RestartTracker,
+        },
+        spawner::Spawner,
     },
     channel::{Channel, PayloadStream},
-    context::StopNotifier,
+    context::{ContextID, StopNotifier},
+    dead_letters::{self, DeadLetter, DeadLetterReason},
     handler::StreamHandler,
 };
 
 mod payload;
-pub(crate) use payload::Payload;
+pub(crate) use payload::{Envelope, PanicSlot, Payload, TaskFuture};
+
+type SleepFn = fn(Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct EnvironmentConfig {
     pub timeout: Option<Duration>,
     pub fail_on_timeout: bool,
+    pub restart_on_timeout: bool,
+    pub restart_intensity: Option<RestartIntensity>,
+    pub backoff: Backoff,
+    pub restart_on_panic: bool,
+    pub stash_capacity: Option<usize>,
+    pub(crate) sleep: Option<SleepFn>,
+}
+
+impl EnvironmentConfig {
+    /// Use the spawner's `sleep` for restart delays.
+    #[cfg_attr(not(any(feature = "tokio", feature = "async-std")), allow(dead_code))]
+    pub(crate) fn with_spawner<A: Actor, S: Spawner<A> + 'static>() -> Self {
+        EnvironmentConfig {
+            sleep: Some(|duration| Box::pin(S::sleep(duration))),
+            ..Default::default()
+        }
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        if let Some(sleep) = self.sleep {
+            sleep(duration).await;
+        } else {
+            futures_timer::Delay::new(duration).await;
+        }
+    }
 }
 
 pub struct Environment<A: Actor, R: RestartStrategy<A> = RestartOnly> {
@@ -33,22 +73,48 @@ pub struct Environment<A: Actor, R: RestartStrategy<A> = RestartOnly> {
 
 impl<A: Actor, R: RestartStrategy<A>> Environment<A, R> {
     pub(crate) fn from_channel(channel: Channel<A>) -> Self {
-        let (tx_running, rx_running) = oneshot::channel::<()>();
+        let (tx_running, rx_running) = oneshot::channel::<StopReason>();
         let ctx = Context {
             id: Default::default(),
             weak_tx: channel.weak_tx(),
             weak_force_tx: channel.weak_force_tx(),
+            mailbox: channel.stats(),
             running: futures::FutureExt::shared(rx_running),
             children: Default::default(),
+            supervision: Default::default(),
             tasks: Default::default(),
+            watching: Default::default(),
+            links: Default::default(),
+            trap_exit: None,
+            stash: Default::default(),
+            behaviors: Default::default(),
         };
-        let (payload_force_tx, payload_tx, payload_stream) = channel.break_up();
+        channel.bind(ctx.id, A::NAME);
+        let channel_stats = channel.stats();
+        let (payload_force_tx, payload_tx, mut mailbox) = channel.break_up();
+        let unstashed = ctx.stash.unstashed();
+        let payload_stream: PayloadStream<A> = futures::stream::poll_fn(Box::new(move |cx| {
+            // unstashed messages go ahead of everything else in the mailbox
+            let mut unstashed = unstashed
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            while let Some(payload) = unstashed.pop_front() {
+                if payload.is_cancelled() {
+                    log::trace!("skipping cancelled unstashed message");
+                    continue;
+                }
+                return Poll::Ready(Some(payload));
+            }
+            drop(unstashed);
+            mailbox.poll_next_unpin(cx)
+        }));
         let stop = StopNotifier(tx_running);
 
         let addr = Addr {
             context_id: ctx.id,
             payload_force_tx,
             payload_tx,
+            mailbox: channel_stats,
             running: ctx.running.clone(),
         };
         Environment {
@@ -62,6 +128,9 @@ impl<A: Actor, R: RestartStrategy<A>> Environment<A, R> {
     }
     #[cfg_attr(not(any(feature = "tokio", feature = "async-std")), allow(dead_code))]
     pub(crate) const fn with_config(mut self, config: EnvironmentConfig) -> Self {
+        if let Some(capacity) = config.stash_capacity {
+            self.ctx.stash.capacity = capacity;
+        }
         self.config = config;
         self
     }
@@ -77,11 +146,46 @@ impl<A: Actor> Environment<A> {
     }
 }
 
-// TODO: consider dynamically deciding timeout based on `Task` vs `DeadlineTask
-async fn timeout_fut(
-    fut: impl Future<Output = ()>,
+fn is_expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| deadline <= Instant::now())
+}
+
+/// Drop a message that missed its deadline, a caller waiting for it gets an error.
+fn skip_expired<A: Actor>(target: ContextID, payload: Payload<A>) {
+    log::debug!("{} skipping expired message", A::NAME);
+    report_expired::<A>(target, payload.message_name());
+}
+
+fn report_expired<A: Actor>(target: ContextID, message: Option<&'static str>) {
+    if let Some(message) = message {
+        dead_letters::report(DeadLetter {
+            target,
+            actor: A::NAME,
+            message,
+            reason: DeadLetterReason::Expired,
+        });
+    }
+}
+
+/// Give up on a handler once its message's deadline passed.
+///
+/// Unlike the actor's [`timeout`](`EnvironmentConfig::timeout`) this only drops the one message,
+/// the actor is neither restarted nor stopped.
+async fn until_deadline<T>(fut: impl Future<Output = T>, deadline: Option<Instant>) -> Option<T> {
+    let Some(deadline) = deadline else {
+        return Some(fut.await);
+    };
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    futures::select! {
+        res = fut.fuse() => Some(res),
+        () = futures_timer::Delay::new(remaining).fuse() => None,
+    }
+}
+
+async fn timeout_fut<T>(
+    fut: impl Future<Output = T>,
     timeout: Option<Duration>,
-) -> crate::DynResult<()> {
+) -> crate::DynResult<T> {
     if let Some(timeout) = timeout {
         futures::select! {
             res = fut.map(Ok).fuse() => res,
@@ -92,28 +196,87 @@ async fn timeout_fut(
     }
 }
 
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"))
+}
+
+/// Drop the stashed messages, the rest of the mailbox is dropped once it is closed.
+///
+/// If the actor panicked, the mailbox is emptied right away
+/// so that the callers of all pending messages learn about the panic.
+fn drop_pending<A: Actor>(
+    ctx: &mut Context<A>,
+    payload_stream: &mut PayloadStream<A>,
+    reason: &StopReason,
+) {
+    let panicked = match reason {
+        StopReason::Panicked(message) => Some(message.as_str()),
+        _ => None,
+    };
+    let mut pending = Vec::new();
+    if panicked.is_some() {
+        while let Some(Some(payload)) = payload_stream.next().now_or_never() {
+            pending.push(payload);
+        }
+    }
+    ctx.stash.report_stopped(ctx.id, A::NAME, pending, panicked);
+}
+
 impl<A: Actor, R: RestartStrategy<A>> Environment<A, R> {
     pub fn create_loop(
         mut self,
         mut actor: A,
     ) -> (impl Future<Output = crate::DynResult<A>>, Addr<A>) {
         let actor_loop = async move {
-            actor.started(&mut self.ctx).await?;
-
-            let timeout = self.config.timeout;
-            while let Some(event) = self.payload_stream.next().await {
-                match event {
-                    Payload::Restart => {
-                        log::trace!("restarting {}", A::NAME);
-                        actor = R::refresh(actor, &mut self.ctx).await?
+            if let Err(error) = actor.started(&mut self.ctx).await {
+                self.stop.notify(StopReason::StartFailed(error.to_string()));
+                return Err(error);
+            }
+
+            let mut restarts =
+                RestartTracker::new(self.config.restart_intensity, self.config.backoff);
+            let reason = loop {
+                let payload = self.payload_stream.next().await;
+                let deadline = payload.as_ref().and_then(Payload::deadline);
+                let message = payload.as_ref().and_then(Payload::message_name);
+                match payload {
+                    Some(Payload::Restart) => {}
+                    Some(payload) if is_expired(deadline) => {
+                        skip_expired(self.ctx.id, payload);
+                        continue;
                     }
-                    Payload::Task(f) => {
+                    Some(payload @ (Payload::Task(_) | Payload::Message(..))) => {
                         log::trace!(name = A::NAME;  "received task");
-                        if let Err(err) = timeout_fut(f(&mut actor, &mut self.ctx), timeout).await {
-                            if self.config.fail_on_timeout {
+                        let task =
+                            AssertUnwindSafe(payload.run(&mut actor, &mut self.ctx)).catch_unwind();
+                        let task = until_deadline(timeout_fut(task, self.config.timeout), deadline);
+                        let Some(outcome) = task.await else {
+                            log::debug!("{} handler ran past the message's deadline", A::NAME);
+                            report_expired::<A>(self.ctx.id, message);
+                            continue;
+                        };
+                        match outcome {
+                            Ok(Ok(())) => continue,
+                            Ok(Err(panic)) => {
+                                let message = panic_message(&*panic);
+                                if !self.config.restart_on_panic {
+                                    log::error!("{} panicked: {message}, exiting", A::NAME);
+                                    break StopReason::Panicked(message);
+                                }
+                                log::warn!("{} panicked: {message}, restarting", A::NAME);
+                            }
+                            Err(err) if self.config.restart_on_timeout => {
+                                log::warn!("{} task took too long: {err}, restarting", A::NAME);
+                            }
+                            Err(err) if self.config.fail_on_timeout => {
                                 log::warn!("{:?} task took too long: {:?}, exiting", A::NAME, err);
-                                return Err(err);
-                            } else {
+                                break StopReason::Timeout;
+                            }
+                            Err(err) => {
                                 log::warn!(
                                     "{:?} actor task took too long: {:?}, ignoring",
                                     A::NAME,
@@ -123,24 +286,84 @@ impl<A: Actor, R: RestartStrategy<A>> Environment<A, R> {
                             }
                         }
                     }
+                    Some(Payload::Stop) => break StopReason::Stopped,
+                    Some(Payload::Exit(reason)) => break reason,
+                    None => break StopReason::AddrsDropped,
+                }
 
-                    Payload::Stop => break,
+                let Ok(delay) = restarts.register(Instant::now()) else {
+                    log::warn!("{} restarted too often, giving up", A::NAME);
+                    break StopReason::TooManyRestarts;
+                };
+                if !delay.is_zero() {
+                    log::trace!("restarting {} in {:?}", A::NAME, delay);
+                    self.config.sleep(delay).await;
                 }
-            }
+                log::trace!("restarting {}", A::NAME);
+                match R::refresh(actor, &mut self.ctx).await {
+                    Ok(refreshed) => actor = refreshed,
+                    Err(error) => {
+                        self.stop.notify(StopReason::StartFailed(error.to_string()));
+                        return Err(error);
+                    }
+                }
+            };
 
-            actor.stopped(&mut self.ctx).await;
+            actor.stopped(&mut self.ctx, reason.clone()).await;
+            drop_pending(&mut self.ctx, &mut self.payload_stream, &reason);
+            // close the mailbox, sending fails once the actor is known to be stopped
+            drop(std::mem::replace(
+                &mut self.payload_stream,
+                futures::stream::poll_fn(Box::new(|_| Poll::Ready(None))),
+            ));
 
-            self.stop.notify();
-            Ok(actor)
+            self.stop.notify(reason.clone());
+            match reason.to_error() {
+                Some(error) => Err(error.into()),
+                None => Ok(actor),
+            }
         };
 
         (actor_loop, self.addr)
     }
 
     pub fn create_loop_on_stream<S>(
+        self,
+        actor: A,
+        stream: S,
+    ) -> (impl Future<Output = crate::DynResult<A>>, Addr<A>)
+    where
+        S: Stream + Unpin + Send + 'static,
+        S::Item: 'static + Send,
+        A: StreamHandler<S::Item>,
+    {
+        self.create_loop_on_streams(actor, stream, || None)
+    }
+
+    /// Like [`create_loop_on_stream`](`Self::create_loop_on_stream`), but the actor can be restarted.
+    ///
+    /// `make_stream` is called once up front and again on every restart,
+    /// the actor then handles the fresh stream instead of the old one.
+    pub fn create_loop_on_stream_factory<F, S>(
+        self,
+        actor: A,
+        mut make_stream: F,
+    ) -> (impl Future<Output = crate::DynResult<A>>, Addr<A>)
+    where
+        F: FnMut() -> S + Send + 'static,
+        S: Stream + Unpin + Send + 'static,
+        S::Item: 'static + Send,
+        A: StreamHandler<S::Item>,
+    {
+        let stream = make_stream();
+        self.create_loop_on_streams(actor, stream, move || Some(make_stream()))
+    }
+
+    fn create_loop_on_streams<S>(
         mut self,
         mut actor: A,
         mut stream: S,
+        mut restart_stream: impl FnMut() -> Option<S> + Send + 'static,
     ) -> (impl Future<Output = crate::DynResult<A>>, Addr<A>)
     where
         S: Stream + Unpin + Send + 'static,
@@ -148,38 +371,136 @@ impl<A: Actor, R: RestartStrategy<A>> Environment<A, R> {
         A: StreamHandler<S::Item>,
     {
         let actor_loop = async move {
-            actor.started(&mut self.ctx).await?;
-            loop {
+            if let Err(error) = actor.started(&mut self.ctx).await {
+                self.stop.notify(StopReason::StartFailed(error.to_string()));
+                return Err(error);
+            }
+            let mut restarts =
+                RestartTracker::new(self.config.restart_intensity, self.config.backoff);
+            let reason = loop {
+                // why the actor is restarted, or stopped if its stream can't be restarted
+                let mut failure = None;
                 futures::select! {
                     event = self.payload_stream.next().fuse() => {
+                        let deadline = event.as_ref().and_then(Payload::deadline);
+                        let message = event.as_ref().and_then(Payload::message_name);
                         match event {
-                            Some(Payload::Task(f)) => f(&mut actor, &mut self.ctx).await,
-                            Some(Payload::Stop)  =>  break,
-                            Some(Payload::Restart)  =>  {
-                                panic!("restart message in streamhandling actor")
-                                // TODO: what does this do with the
-                                // log::warn!("ignoring restart message in streamhandling actor")
+                            Some(payload) if is_expired(deadline) => {
+                                skip_expired(self.ctx.id, payload);
+                                continue;
                             },
-                            None =>  break
+                            Some(payload @ (Payload::Task(_) | Payload::Message(..))) => {
+                                let task = AssertUnwindSafe(payload.run(&mut actor, &mut self.ctx)).catch_unwind();
+                                let task = until_deadline(timeout_fut(task, self.config.timeout), deadline);
+                                let Some(outcome) = task.await else {
+                                    log::debug!("{} handler ran past the message's deadline", A::NAME);
+                                    report_expired::<A>(self.ctx.id, message);
+                                    continue;
+                                };
+                                match outcome {
+                                    Ok(Ok(())) => continue,
+                                    Ok(Err(panic)) => {
+                                        let message = panic_message(&*panic);
+                                        if !self.config.restart_on_panic {
+                                            log::error!("{} panicked: {message}, exiting", A::NAME);
+                                            break StopReason::Panicked(message);
+                                        }
+                                        failure = Some(StopReason::Panicked(message));
+                                    }
+                                    Err(err) if self.config.restart_on_timeout => {
+                                        log::warn!("{} task took too long: {err}, restarting", A::NAME);
+                                        failure = Some(StopReason::Timeout);
+                                    }
+                                    Err(err) if self.config.fail_on_timeout => {
+                                        log::warn!("{} task took too long: {err}, exiting", A::NAME);
+                                        break StopReason::Timeout;
+                                    }
+                                    Err(err) => {
+                                        log::warn!("{} task took too long: {err}, ignoring", A::NAME);
+                                        continue;
+                                    }
+                                }
+                            },
+                            Some(Payload::Stop)  =>  break StopReason::Stopped,
+                            Some(Payload::Exit(reason))  =>  break reason,
+                            Some(Payload::Restart)  =>  {},
+                            None =>  break StopReason::AddrsDropped
                         }
                     },
                     stream_msg = stream.next().fuse() => {
                         let Some(msg) = stream_msg else {
                             // stream is done, actor is done
-                            break
+                            break StopReason::StreamFinished
                         };
-                        StreamHandler::handle(&mut actor, &mut self.ctx, msg).await;
+                        let task = StreamHandler::handle(&mut actor, &mut self.ctx, msg);
+                        match timeout_fut(task, self.config.timeout).await {
+                            Ok(()) => continue,
+                            Err(err) if self.config.restart_on_timeout => {
+                                log::warn!("{} stream item took too long: {err}, restarting", A::NAME);
+                                failure = Some(StopReason::Timeout);
+                            }
+                            Err(err) if self.config.fail_on_timeout => {
+                                log::warn!("{} stream item took too long: {err}, exiting", A::NAME);
+                                break StopReason::Timeout;
+                            }
+                            Err(err) => {
+                                log::warn!("{} stream item took too long: {err}, ignoring", A::NAME);
+                                continue;
+                            }
+                        }
                     },
-                    complete => break,
-                    // default => break, // TODO: should this be here?
+                    complete => break StopReason::StreamFinished,
                 }
-            }
+
+                // a one-off stream can't be subscribed to again
+                let Some(fresh_stream) = restart_stream() else {
+                    if let Some(reason) = failure {
+                        log::error!(
+                            "{} failed with {reason:?} on a one-off stream, exiting",
+                            A::NAME
+                        );
+                        break reason;
+                    }
+                    log::warn!("ignoring restart message in streamhandling actor");
+                    continue;
+                };
+                if let Some(StopReason::Panicked(message)) = &failure {
+                    log::warn!("{} panicked: {message}, restarting", A::NAME);
+                }
+
+                let Ok(delay) = restarts.register(Instant::now()) else {
+                    log::warn!("{} restarted too often, giving up", A::NAME);
+                    break StopReason::TooManyRestarts;
+                };
+                if !delay.is_zero() {
+                    log::trace!("restarting {} in {:?}", A::NAME, delay);
+                    self.config.sleep(delay).await;
+                }
+                log::trace!("restarting {} on a fresh stream", A::NAME);
+                match R::refresh(actor, &mut self.ctx).await {
+                    Ok(refreshed) => actor = refreshed,
+                    Err(error) => {
+                        self.stop.notify(StopReason::StartFailed(error.to_string()));
+                        return Err(error);
+                    }
+                }
+                stream = fresh_stream;
+            };
 
             actor.finished(&mut self.ctx).await;
-            actor.stopped(&mut self.ctx).await;
+            actor.stopped(&mut self.ctx, reason.clone()).await;
+            drop_pending(&mut self.ctx, &mut self.payload_stream, &reason);
+            // close the mailbox, sending fails once the actor is known to be stopped
+            drop(std::mem::replace(
+                &mut self.payload_stream,
+                futures::stream::poll_fn(Box::new(|_| Poll::Ready(None))),
+            ));
 
-            self.stop.notify();
-            Ok(actor)
+            self.stop.notify(reason.clone());
+            match reason.to_error() {
+                Some(error) => Err(error.into()),
+                None => Ok(actor),
+            }
         };
 
         (actor_loop, self.addr)
@@ -211,7 +532,7 @@ mod tests {
     #[derive(Default)]
     struct GoodActor {
         started: bool,
-        stopped: bool,
+        stopped: Option<StopReason>,
         count: i32,
     }
 
@@ -221,8 +542,8 @@ mod tests {
             Ok(())
         }
 
-        async fn stopped(&mut self, _: &mut Context<Self>) {
-            self.stopped = true;
+        async fn stopped(&mut self, _: &mut Context<Self>, reason: StopReason) {
+            self.stopped = Some(reason);
         }
     }
 
@@ -264,7 +585,7 @@ mod tests {
             let task = tokio::spawn(event_loop);
             addr.stop().unwrap();
             let actor = task.await.unwrap().unwrap();
-            assert!(actor.stopped);
+            assert_eq!(actor.stopped, Some(StopReason::Stopped));
         }
 
         #[test_log::test(tokio::test)]
@@ -274,6 +595,16 @@ mod tests {
             addr.stop().unwrap();
             let error = task.await.unwrap().map(|_| ()).map_err(|e| e.to_string());
             assert_eq!(error, Err(String::from("failed")), "start should fail");
+            assert_eq!(addr.await, StopReason::StartFailed(String::from("failed")));
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn stops_when_addrs_dropped() {
+            let (event_loop, addr) = Environment::unbounded().create_loop(GoodActor::default());
+            let task = tokio::spawn(event_loop);
+            drop(addr);
+            let actor = task.await.unwrap().unwrap();
+            assert_eq!(actor.stopped, Some(StopReason::AddrsDropped));
         }
     }
 
@@ -304,7 +635,7 @@ mod tests {
             let task = tokio::spawn(event_loop);
             addr.stop().unwrap();
             let actor = task.await.unwrap().unwrap();
-            assert!(actor.stopped);
+            assert_eq!(actor.stopped, Some(StopReason::Stopped));
         }
 
         #[test_log::test(tokio::test)]
@@ -325,12 +656,10 @@ mod tests {
             tokio::time::sleep(std::time::Duration::from_millis(400)).await;
 
             // TODO: should the stream always stop the actor?
-            assert!(matches!(
-                addr.stop().unwrap_err(),
-                ActorError::AsyncSendError(_)
-            ));
-            assert!(
-                addr2.await.is_ok(),
+            assert_eq!(addr.stop().unwrap_err(), ActorError::AlreadyStopped);
+            assert_eq!(
+                addr2.await,
+                StopReason::StreamFinished,
                 "other address should be cleanly awaitable"
             );
 
@@ -367,12 +696,16 @@ mod tests {
                 Ok(())
             }
 
-            async fn stopped(&mut self, _: &mut Context<Self>) {
+            async fn stopped(&mut self, _: &mut Context<Self>, _: StopReason) {
                 self.stopped_count += 1;
                 eprintln!("stopped: {:?}", self);
             }
         }
 
+        impl StreamHandler<i32> for RestartCounter {
+            async fn handle(&mut self, _: &mut Context<Self>, _: i32) {}
+        }
+
         #[test_log::test(tokio::test)]
         async fn restarts_actor() {
             let counter = RestartCounter::new();
@@ -386,6 +719,43 @@ mod tests {
             assert_eq!(actor.stopped_count, 3);
         }
 
+        #[test_log::test(tokio::test)]
+        async fn restarts_stream_handler_on_fresh_stream() {
+            use std::sync::{
+                Arc,
+                atomic::{AtomicUsize, Ordering},
+            };
+
+            let subscriptions = Arc::new(AtomicUsize::new(0));
+            let subscribed = Arc::clone(&subscriptions);
+            let (event_loop, mut addr) = Environment::unbounded().create_loop_on_stream_factory(
+                RestartCounter::new(),
+                move || {
+                    subscribed.fetch_add(1, Ordering::SeqCst);
+                    futures::stream::pending::<i32>()
+                },
+            );
+            let task = tokio::spawn(event_loop);
+            addr.restart().unwrap();
+            addr.restart().unwrap();
+            addr.stop().unwrap();
+            let actor = task.await.unwrap().unwrap();
+            assert_eq!(actor.started_count, 3);
+            assert_eq!(subscriptions.load(Ordering::SeqCst), 3);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn ignores_restart_without_stream_factory() {
+            let (event_loop, mut addr) = Environment::unbounded()
+                .create_loop_on_stream(RestartCounter::new(), futures::stream::pending::<i32>());
+            let task = tokio::spawn(event_loop);
+            addr.restart().unwrap();
+            addr.stop().unwrap();
+            let actor = task.await.unwrap().unwrap();
+            assert_eq!(actor.started_count, 1);
+            assert_eq!(actor.stopped_count, 1);
+        }
+
         #[test_log::test(tokio::test)]
         async fn recreates_actor() {
             let counter = RestartCounter::new();
@@ -398,12 +768,143 @@ mod tests {
             assert_eq!(actor.started_count, 1);
             assert_eq!(actor.stopped_count, 1, "should only be stopped once");
         }
+
+        #[test_log::test(tokio::test)]
+        async fn gives_up_after_too_many_restarts() {
+            let config = EnvironmentConfig {
+                restart_intensity: Some(RestartIntensity {
+                    max_restarts: 2,
+                    within: Duration::from_secs(10),
+                }),
+                ..Default::default()
+            };
+            let (event_loop, mut addr) = Environment::unbounded()
+                .with_config(config)
+                .create_loop(RestartCounter::new());
+            let task = tokio::spawn(event_loop);
+            addr.restart().unwrap();
+            addr.restart().unwrap();
+            addr.restart().unwrap();
+            let error = task.await.unwrap().unwrap_err();
+            assert_eq!(error.to_string(), ActorError::TooManyRestarts.to_string());
+            assert_eq!(addr.await, StopReason::TooManyRestarts);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn waits_before_restarting() {
+            let config = EnvironmentConfig {
+                backoff: Backoff::Fixed(Duration::from_millis(100)),
+                ..Default::default()
+            };
+            let (event_loop, mut addr) = Environment::unbounded()
+                .with_config(config)
+                .create_loop(RestartCounter::new());
+            let task = tokio::spawn(event_loop);
+            let before = Instant::now();
+            addr.restart().unwrap();
+            addr.stop().unwrap();
+            let actor = task.await.unwrap().unwrap();
+            assert!(before.elapsed() >= Duration::from_millis(100));
+            assert_eq!(actor.started_count, 2);
+        }
+    }
+
+    mod panics {
+        use super::*;
+        use crate::{Handler, Message, RestartableActor};
+
+        #[derive(Debug, Default)]
+        struct Panicky {
+            started_count: usize,
+        }
+
+        impl Actor for Panicky {
+            async fn started(&mut self, _: &mut Context<Self>) -> DynResult {
+                self.started_count += 1;
+                Ok(())
+            }
+        }
+        impl RestartableActor for Panicky {}
+
+        struct Panic;
+        impl Message for Panic {
+            type Response = ();
+        }
+
+        impl Handler<Panic> for Panicky {
+            async fn handle(&mut self, _: &mut Context<Self>, _: Panic) {
+                panic!("boom")
+            }
+        }
+
+        struct Starts;
+        impl Message for Starts {
+            type Response = usize;
+        }
+
+        impl Handler<Starts> for Panicky {
+            async fn handle(&mut self, _: &mut Context<Self>, _: Starts) -> usize {
+                self.started_count
+            }
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn stops_with_panic_error() {
+            let (event_loop, addr) = Environment::unbounded().create_loop(Panicky::default());
+            let task = tokio::spawn(event_loop);
+            assert_eq!(
+                addr.call(Panic).await.unwrap_err(),
+                ActorError::Panicked(String::from("boom"))
+            );
+            let error = task.await.unwrap().unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                ActorError::Panicked(String::from("boom")).to_string()
+            );
+            assert!(addr.ping().await.is_err());
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn pending_calls_fail_with_panic_error() {
+            let (event_loop, addr) = Environment::unbounded().create_loop(Panicky::default());
+            let panicking = addr.call(Panic);
+            let pending = addr.call(Starts);
+            let task = tokio::spawn(event_loop);
+
+            let (panicking, pending) = futures::join!(panicking, pending);
+            let boom = ActorError::Panicked(String::from("boom"));
+            assert_eq!(panicking.unwrap_err(), boom);
+            assert_eq!(pending.unwrap_err(), boom);
+            assert!(task.await.unwrap().is_err());
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn restarts_on_panic() {
+            let config = EnvironmentConfig {
+                restart_on_panic: true,
+                ..Default::default()
+            };
+            let (event_loop, mut addr) = Environment::unbounded()
+                .with_config(config)
+                .create_loop(Panicky::default());
+            let task = tokio::spawn(event_loop);
+            assert_eq!(
+                addr.call(Panic).await.unwrap_err(),
+                ActorError::Panicked(String::from("boom"))
+            );
+            assert!(addr.ping().await.is_ok(), "same addr should still work");
+            addr.stop().unwrap();
+            let actor = task.await.unwrap().unwrap();
+            assert_eq!(actor.started_count, 2);
+        }
     }
 
     #[cfg(any(feature = "tokio", feature = "async-std"))]
     mod timeout {
         use std::time::Duration;
 
+        use futures::StreamExt as _;
+
         use crate::{RestartableActor, error::ActorError, prelude::*};
 
         cfg_if::cfg_if! {
@@ -429,7 +930,7 @@ mod tests {
                 Ok(())
             }
 
-            async fn stopped(&mut self, _ctx: &mut Context<Self>) {
+            async fn stopped(&mut self, _ctx: &mut Context<Self>, _: StopReason) {
                 println!("[ SleepyActor {} ] stopped", self.0);
             }
         }
@@ -445,6 +946,27 @@ mod tests {
         // TODO: can we encode the restart strategy in an associated type or as a trait function?
         impl RestartableActor for SleepyActor {}
 
+        impl StreamHandler<Duration> for SleepyActor {
+            async fn handle(&mut self, _ctx: &mut Context<Self>, duration: Duration) {
+                sleep(duration).await;
+            }
+        }
+
+        /// One long stream item on the first subscription, nothing afterwards.
+        fn oversleeping() -> impl FnMut() -> futures::stream::BoxStream<'static, Duration> + Send {
+            let mut first = true;
+            move || {
+                let items = if std::mem::take(&mut first) {
+                    vec![Duration::from_secs(60)]
+                } else {
+                    vec![]
+                };
+                futures::stream::iter(items)
+                    .chain(futures::stream::pending())
+                    .boxed()
+            }
+        }
+
         #[test_log::test(tokio::test)]
         async fn no_timeout() {
             // normal case, tasks take long
@@ -481,7 +1003,7 @@ mod tests {
             assert!(addr.call(Sleep(Duration::from_secs(0))).await.is_ok());
             eprintln!("SleepyActor 2 is still alive, stopping");
             assert!(addr.to_addr().stop().is_ok());
-            assert!(addr.join().await.is_some());
+            assert!(matches!(addr.join().await, (Some(_), StopReason::Stopped)));
         }
 
         #[test_log::test(tokio::test)]
@@ -506,8 +1028,168 @@ mod tests {
                     .await
                     .is_err()
             );
-            assert!(addr.join().await.is_none());
+            assert!(matches!(addr.join().await, (None, StopReason::Timeout)));
             assert!(addr.to_addr().stop().is_err());
         }
+
+        #[test_log::test(tokio::test)]
+        async fn timeout_and_restart() {
+            let mut addr = crate::build(SleepyActor(3))
+                .bounded(1)
+                .timeout(Duration::from_millis(100))
+                .fail_on_timeout(true)
+                .restart_on_timeout(true)
+                .recreate_from_default()
+                .spawn_owning();
+            assert!(matches!(
+                addr.call(Sleep(Duration::from_secs(4))).await.unwrap_err(),
+                ActorError::Canceled(_)
+            ));
+            println!("SleepyActor 3 was recreated and still accepts messages");
+            assert!(addr.call(Sleep(Duration::from_secs(0))).await.is_ok());
+            assert!(addr.to_addr().stop().is_ok());
+            assert!(matches!(
+                addr.join().await,
+                (Some(SleepyActor(0)), StopReason::Stopped)
+            ));
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn stream_item_timeout_and_fail() {
+            let mut addr = crate::build(SleepyActor(4))
+                .bounded(1)
+                .timeout(Duration::from_millis(100))
+                .fail_on_timeout(true)
+                .with_stream_factory(oversleeping())
+                .spawn_owning();
+            assert!(matches!(addr.join().await, (None, StopReason::Timeout)));
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn stream_item_timeout_and_restart() {
+            let mut addr = crate::build(SleepyActor(5))
+                .bounded(1)
+                .timeout(Duration::from_millis(100))
+                .restart_on_timeout(true)
+                .recreate_from_default()
+                .with_stream_factory(oversleeping())
+                .spawn_owning();
+            sleep(Duration::from_millis(300)).await;
+            println!("SleepyActor 5 was recreated on a fresh stream");
+            assert!(addr.call(Sleep(Duration::from_secs(0))).await.is_ok());
+            assert!(addr.to_addr().stop().is_ok());
+            assert!(matches!(
+                addr.join().await,
+                (Some(SleepyActor(0)), StopReason::Stopped)
+            ));
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    mod deadline {
+        use std::time::{Duration, Instant};
+
+        use crate::{error::ActorError, prelude::*};
+
+        #[derive(Debug, Default)]
+        struct Notes(Vec<u32>);
+        impl Actor for Notes {}
+
+        struct Sleep(Duration);
+        impl Message for Sleep {
+            type Response = ();
+            const DEADLINE: Option<Duration> = Some(Duration::from_millis(50));
+        }
+
+        struct Note(u32);
+        impl Message for Note {
+            type Response = ();
+        }
+
+        struct Count;
+        impl Message for Count {
+            type Response = usize;
+        }
+
+        impl Handler<Sleep> for Notes {
+            async fn handle(&mut self, _ctx: &mut Context<Self>, Sleep(duration): Sleep) {
+                tokio::time::sleep(duration).await;
+            }
+        }
+
+        impl Handler<Note> for Notes {
+            async fn handle(&mut self, _ctx: &mut Context<Self>, Note(n): Note) {
+                self.0.push(n);
+            }
+        }
+
+        impl Handler<Count> for Notes {
+            async fn handle(&mut self, _ctx: &mut Context<Self>, _: Count) -> usize {
+                self.0.len()
+            }
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn expired_messages_are_skipped() {
+            let addr = Notes::default().spawn_owning();
+            addr.send(Sleep(Duration::from_millis(40))).await.unwrap();
+
+            let soon = Instant::now() + Duration::from_millis(10);
+            addr.send_with_deadline(Note(1), soon).await.unwrap();
+            addr.send(Note(2)).await.unwrap();
+            assert_eq!(
+                addr.call_with_deadline(Count, soon).await,
+                Err(ActorError::DeadlineExceeded)
+            );
+
+            let later = Instant::now() + Duration::from_secs(1);
+            assert_eq!(addr.call_with_deadline(Count, later).await, Ok(1));
+            assert_eq!(addr.consume().await.unwrap().0, vec![2]);
+        }
+
+        impl StreamHandler<u32> for Notes {
+            async fn handle(&mut self, _ctx: &mut Context<Self>, n: u32) {
+                self.0.push(n);
+            }
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn deadline_limits_handler() {
+            // a missed deadline only drops the message, it does not count as the actor timing out
+            let addr = crate::build(Notes::default())
+                .unbounded()
+                .timeout(Duration::from_secs(10))
+                .fail_on_timeout(true)
+                .spawn_owning();
+
+            let start = Instant::now();
+            assert_eq!(
+                addr.call(Sleep(Duration::from_secs(5))).await,
+                Err(ActorError::DeadlineExceeded)
+            );
+            assert!(start.elapsed() < Duration::from_secs(1));
+
+            // the actor is still around
+            addr.send(Note(1)).await.unwrap();
+            assert_eq!(addr.consume().await.unwrap().0, vec![1]);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn deadline_limits_stream_actor_handler() {
+            let addr = crate::build(Notes::default())
+                .on_stream(futures::StreamExt::chain(
+                    futures::stream::iter([1, 2]),
+                    futures::stream::pending(),
+                ))
+                .spawn();
+
+            let start = Instant::now();
+            assert_eq!(
+                addr.call(Sleep(Duration::from_secs(5))).await,
+                Err(ActorError::DeadlineExceeded)
+            );
+            assert!(start.elapsed() < Duration::from_secs(1));
+            assert_eq!(addr.call(Count).await, Ok(2));
+        }
     }
 }
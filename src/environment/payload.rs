@@ -1,15 +1,115 @@
-use std::{future::Future, pin::Pin};
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
 
-use crate::{Actor, Context};
+use futures::{FutureExt as _, channel::oneshot};
 
-type TaskFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+use crate::{
+    Actor, Context, Handler, Message, StopReason,
+    addr::{Cancellation, ConflationKey, Priority},
+    context::behavior::{self, Dispatcher},
+};
+
+pub(crate) type TaskFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
 
 type TaskFn<A> =
     Box<dyn for<'a> FnOnce(&'a mut A, &'a mut Context<A>) -> TaskFuture<'a> + Send + 'static>;
 
+/// Like [`TaskFn`], but gets its envelope back, so that a stashed message keeps it.
+type MessageFn<A> = Box<
+    dyn for<'a> FnOnce(&'a mut A, &'a mut Context<A>, Envelope) -> TaskFuture<'a> + Send + 'static,
+>;
+
+/// Tells a caller that its call was dropped because the actor panicked.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PanicSlot(Arc<OnceLock<String>>);
+
+impl PanicSlot {
+    pub fn set(&self, message: &str) {
+        let _ = self.0.set(message.to_owned());
+    }
+
+    pub fn get(&self) -> Option<&str> {
+        self.0.get().map(String::as_str)
+    }
+}
+
+/// What the mailbox knows about a message without handling it.
+#[derive(Debug)]
+pub(crate) struct Envelope {
+    pub priority: Priority,
+    pub conflation_key: Option<ConflationKey>,
+    /// The type name of the message, reported if it ends up as a [`DeadLetter`](`crate::DeadLetter`).
+    pub message: &'static str,
+    /// Set for calls whose caller can give up, see [`CallHandle`](`crate::CallHandle`).
+    pub cancellation: Option<Cancellation>,
+    /// When the message is no longer worth handling, see [`Message::DEADLINE`].
+    pub deadline: Option<Instant>,
+    /// Set for calls, so that the caller learns about a panic instead of just being canceled.
+    pub panic: Option<PanicSlot>,
+}
+
+impl Envelope {
+    pub fn new<M: Message>() -> Self {
+        Self {
+            priority: M::PRIORITY,
+            conflation_key: None,
+            message: std::any::type_name::<M>(),
+            cancellation: None,
+            deadline: M::DEADLINE.and_then(|deadline| Instant::now().checked_add(deadline)),
+            panic: None,
+        }
+    }
+
+    /// The envelope of a message whose caller waits for the response.
+    pub fn call<M: Message>() -> Self {
+        Self {
+            panic: Some(PanicSlot::default()),
+            ..Self::new::<M>()
+        }
+    }
+
+    /// The envelope of a stream item, which does not have to implement [`Message`].
+    #[cfg_attr(
+        not(any(feature = "tokio", feature = "async-std", feature = "custom_runtime")),
+        allow(dead_code)
+    )]
+    pub fn item<T>() -> Self {
+        Self {
+            priority: Priority::Normal,
+            conflation_key: None,
+            message: std::any::type_name::<T>(),
+            cancellation: None,
+            deadline: None,
+            panic: None,
+        }
+    }
+
+    /// The envelope of a message that is sent without waiting for a response.
+    pub fn of<M: Message<Response = ()>>(msg: &M) -> Self {
+        Self {
+            conflation_key: msg.conflation_key(),
+            ..Self::new::<M>()
+        }
+    }
+}
+
 pub(crate) enum Payload<A> {
+    /// A message sent to the actor, subject to the mailbox's capacity.
+    Message(MessageFn<A>, Envelope),
+    /// Internal work like notifications, never dropped or rejected by a full mailbox.
     Task(TaskFn<A>),
     Stop,
+    /// Stop with a reason other than [`StopReason::Stopped`].
+    #[cfg_attr(
+        not(any(feature = "tokio", feature = "async-std", feature = "custom_runtime")),
+        allow(dead_code)
+    )]
+    Exit(StopReason),
     Restart,
 }
 
@@ -20,4 +120,164 @@ impl<A: Actor> Payload<A> {
     {
         Self::Task(Box::new(f))
     }
+
+    pub fn message<F>(envelope: Envelope, f: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a mut A, &'a mut Context<A>) -> TaskFuture<'a> + Send + 'static,
+    {
+        Self::enveloped(envelope, move |actor, ctx, _| f(actor, ctx))
+    }
+
+    /// A message whose handler gets the envelope back.
+    fn enveloped<F>(envelope: Envelope, f: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a mut A, &'a mut Context<A>, Envelope) -> TaskFuture<'a>
+            + Send
+            + 'static,
+    {
+        Self::Message(Box::new(f), envelope)
+    }
+
+    /// A message that is handled without responding.
+    pub fn handle<M>(envelope: Envelope, msg: M) -> Self
+    where
+        A: Handler<M>,
+        M: Message<Response = ()>,
+    {
+        Self::handle_with(envelope, msg, behavior::dispatch)
+    }
+
+    /// A message that is handled without responding, routed by `dispatch`.
+    pub fn handle_with<M>(envelope: Envelope, msg: M, dispatch: Dispatcher<A, M>) -> Self
+    where
+        M: Message<Response = ()>,
+    {
+        Self::enveloped(envelope, move |actor, ctx, envelope| {
+            Box::pin(async move {
+                ctx.stash.begin::<M>();
+                dispatch(actor, ctx, msg).await;
+                if let Some(msg) = ctx.stash.take::<M>() {
+                    ctx.stash.push(Self::handle_with(envelope, msg, dispatch));
+                }
+            })
+        })
+    }
+
+    /// A message whose response is sent to `responder`.
+    ///
+    /// If the handler [stashes](`Context::stash`) the message, the responder and the envelope are stashed along with it.
+    /// If the handler panics, the caller is told so via the envelope's [`PanicSlot`].
+    pub fn call<M>(envelope: Envelope, msg: M, responder: oneshot::Sender<M::Response>) -> Self
+    where
+        A: Handler<M>,
+        M: Message,
+    {
+        Self::call_with(envelope, msg, responder, behavior::dispatch)
+    }
+
+    /// A message whose response is sent to `responder`, routed by `dispatch`.
+    pub fn call_with<M: Message>(
+        envelope: Envelope,
+        msg: M,
+        responder: oneshot::Sender<M::Response>,
+        dispatch: Dispatcher<A, M>,
+    ) -> Self {
+        Self::enveloped(envelope, move |actor, ctx, envelope| {
+            Box::pin(async move {
+                ctx.stash.begin::<M>();
+                let response = match AssertUnwindSafe(dispatch(actor, ctx, msg))
+                    .catch_unwind()
+                    .await
+                {
+                    Ok(response) => response,
+                    Err(panic) => {
+                        if let Some(slot) = &envelope.panic {
+                            slot.set(&super::panic_message(&*panic));
+                        }
+                        // the event loop decides whether to restart or to stop
+                        std::panic::resume_unwind(panic)
+                    }
+                };
+                if let Some(msg) = ctx.stash.take::<M>() {
+                    ctx.stash
+                        .push(Self::call_with(envelope, msg, responder, dispatch));
+                } else if let Some(response) = response {
+                    let _ = responder.send(response);
+                }
+            })
+        })
+    }
+}
+
+impl<A> Payload<A> {
+    /// Start handling a message or task.
+    ///
+    /// Stopping and restarting is up to the event loop, so there is nothing to run for them.
+    pub fn run<'a>(self, actor: &'a mut A, ctx: &'a mut Context<A>) -> TaskFuture<'a> {
+        match self {
+            Self::Task(f) => f(actor, ctx),
+            Self::Message(f, envelope) => f(actor, ctx, envelope),
+            Self::Stop | Self::Exit(_) | Self::Restart => Box::pin(async {}),
+        }
+    }
+
+    /// Where a message is queued in a priority mailbox, internal tasks are queued with normal messages.
+    ///
+    /// Stopping and restarting goes ahead of all messages in a priority mailbox, see [`is_control`](`Self::is_control`).
+    pub const fn priority(&self) -> Priority {
+        match self {
+            Self::Message(_, envelope) => envelope.priority,
+            Self::Task(_) | Self::Stop | Self::Exit(_) | Self::Restart => Priority::Normal,
+        }
+    }
+
+    /// Whether the payload stops or restarts the actor.
+    pub const fn is_control(&self) -> bool {
+        matches!(self, Self::Stop | Self::Exit(_) | Self::Restart)
+    }
+
+    /// Whether the payload counts towards a bounded mailbox's capacity.
+    pub const fn is_message(&self) -> bool {
+        matches!(self, Self::Message(..))
+    }
+
+    /// The envelope of a message, internal payloads have none.
+    pub const fn envelope(&self) -> Option<&Envelope> {
+        match self {
+            Self::Message(_, envelope) => Some(envelope),
+            _ => None,
+        }
+    }
+
+    /// The type name of a message, reported if it ends up as a [`DeadLetter`](`crate::DeadLetter`).
+    pub fn message_name(&self) -> Option<&'static str> {
+        self.envelope().map(|envelope| envelope.message)
+    }
+
+    /// When a message is no longer worth handling.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.envelope().and_then(|envelope| envelope.deadline)
+    }
+
+    /// Tell a waiting caller that the actor panicked before it got to the message.
+    pub fn mark_panicked(&self, message: &str) {
+        if let Some(slot) = self.envelope().and_then(|envelope| envelope.panic.as_ref()) {
+            slot.set(message);
+        }
+    }
+
+    /// Whether the caller gave up on the message before it was handled.
+    pub fn is_cancelled(&self) -> bool {
+        self.envelope()
+            .and_then(|envelope| envelope.cancellation.as_ref())
+            .is_some_and(Cancellation::is_cancelled)
+    }
+
+    /// Which queued message this payload replaces in a conflating mailbox.
+    pub const fn conflation_key(&self) -> Option<&ConflationKey> {
+        match self {
+            Self::Message(_, envelope) => envelope.conflation_key.as_ref(),
+            _ => None,
+        }
+    }
 }
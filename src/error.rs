@@ -23,6 +23,26 @@ pub enum ActorError {
     #[error("Actor already stopped")]
     AlreadyStopped,
 
+    /// The actor's bounded mailbox is full and its [`OverflowPolicy`](`crate::OverflowPolicy`) rejects the message.
+    #[error("Mailbox is full")]
+    MailboxFull,
+
+    /// The actor's [stash](`crate::Context::stash`) is full.
+    #[error("Stash is full")]
+    StashFull,
+
+    /// Only the message that is currently being handled can be [stashed](`crate::Context::stash`), and only once.
+    #[error("Only the message being handled can be stashed")]
+    NotStashable,
+
+    /// A [`Pool`](`crate::Pool`) or [`HashRouter`](`crate::HashRouter`) without workers can't handle messages.
+    #[error("Pool has no workers")]
+    EmptyPool,
+
+    /// A [`ScatterGather`](`crate::ScatterGather`) without callers has no one to ask.
+    #[error("No recipients to call")]
+    NoRecipients,
+
     #[error("Service not found")]
     ServiceNotFound,
 
@@ -31,4 +51,31 @@ pub enum ActorError {
 
     #[error("Actor's task took too long to complete")]
     Timeout,
+
+    /// No response arrived in time, see [`Addr::call_timeout`](`crate::Addr::call_timeout`).
+    #[error("Call timed out")]
+    CallTimeout,
+
+    /// The message was not handled before its deadline, see [`Message::DEADLINE`](`crate::Message::DEADLINE`).
+    #[error("Deadline exceeded")]
+    DeadlineExceeded,
+
+    /// Too many calls of a [`ScatterGather`](`crate::ScatterGather`) failed.
+    #[error("Only {reached} of {quorum} calls succeeded")]
+    QuorumNotReached { quorum: usize, reached: usize },
+
+    /// A handler panicked while processing a message.
+    ///
+    /// Callers get this for the call whose handler panicked,
+    /// and for all their calls that were still pending when the actor stopped because of it.
+    #[error("Handler panicked: {0}")]
+    Panicked(String),
+
+    /// The actor was restarted more often than its [`RestartIntensity`](`crate::RestartIntensity`) allows.
+    #[error("Actor was restarted too often")]
+    TooManyRestarts,
+
+    /// An actor [linked](`crate::Context::link`) to this one failed.
+    #[error("Linked actor {0} failed")]
+    LinkFailed(crate::context::ContextID),
 }
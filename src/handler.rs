@@ -23,6 +23,9 @@ pub trait StreamHandler<M: 'static>: Actor {
         msg: M,
     ) -> impl futures::Future<Output = ()> + Send;
 
+    /// Called once a stream of `M` has ended.
+    ///
+    /// For streams added via [`Context::add_stream`] this is called once per stream.
     #[allow(unused)]
     fn finished(&mut self, ctx: &mut Context<Self>) -> impl Future<Output = ()> + Send {
         async {}
@@ -47,6 +47,7 @@ mod actor;
 mod addr;
 mod channel;
 mod context;
+mod dead_letters;
 mod environment;
 pub mod error;
 
@@ -55,32 +56,52 @@ pub use hannibal_derive::message;
 #[cfg(any(feature = "tokio", feature = "async-std"))]
 mod broker;
 mod handler;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+mod router;
 
 // TODO: flatten module structure
 pub use self::{
     actor::{
-        Actor, DynResult, RestartableActor,
+        Actor, Backoff, DynResult, RestartIntensity, RestartableActor, StopReason,
         service::{self, Service},
         spawner,
     },
     addr::{
-        Addr, Message, OwningAddr, caller::Caller, sender::Sender, weak_addr::WeakAddr,
+        ActorSink, Addr, CallHandle, Conflate, ConflationKey, Message, OwningAddr, Priority,
+        ScatterGather, caller::Caller, sender::Sender, weak_addr::WeakAddr,
         weak_caller::WeakCaller, weak_sender::WeakSender,
     },
-    context::Context,
+    channel::{MailboxStats, OverflowPolicy},
+    context::{
+        Context, ContextID, Exit, SupervisionStrategy,
+        behavior::{Behavior, BehaviorHandler, Handles, Unhandled},
+    },
+    dead_letters::{DeadLetter, DeadLetterReason},
     handler::{Handler, StreamHandler},
 };
 
 #[cfg(any(feature = "tokio", feature = "async-std"))]
-pub use actor::build;
+pub use actor::{
+    build,
+    fsm::{self, FsmActor},
+};
+
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "custom_runtime"))]
+pub use context::{StreamHandle, Terminated};
 
 #[cfg(any(feature = "tokio", feature = "async-std"))]
 pub use broker::Broker;
 
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub use dead_letters::DeadLetters;
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub use router::{HashRouter, Pool, Routing, RoutingKey};
+
 pub mod prelude {
     //! Re-exports the most commonly used traits and types.
     pub use crate::{
-        actor::{Actor, DynResult, service::Service},
+        actor::{Actor, DynResult, StopReason, service::Service},
         addr::{Addr, Message, sender::Sender, weak_caller::WeakCaller, weak_sender::WeakSender},
         context::Context,
         handler::{Handler, StreamHandler},
@@ -0,0 +1,6 @@
+//! Spreading messages across several actors of the same kind.
+mod hashing;
+mod pool;
+
+pub use hashing::{HashRouter, RoutingKey};
+pub use pool::{Pool, Routing};
@@ -0,0 +1,234 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::{DefaultHasher, Hash, Hasher as _},
+};
+
+use crate::{
+    Actor, Addr, Handler, Message,
+    context::ContextID,
+    error::{ActorError, Result},
+};
+
+/// Messages that belong to some entity, e.g. a user or a session.
+///
+/// A [`HashRouter`] sends all messages with equal keys to the same worker.
+///
+/// ```rust
+/// # use hannibal::{RoutingKey, message};
+/// #[message]
+/// struct Deposit {
+///     account: u64,
+///     amount: u64,
+/// }
+///
+/// impl RoutingKey for Deposit {
+///     type Key = u64;
+///     fn routing_key(&self) -> Self::Key {
+///         self.account
+///     }
+/// }
+/// ```
+pub trait RoutingKey: Message {
+    /// Messages with equal keys are handled by the same worker.
+    type Key: Hash;
+
+    /// The key of this message.
+    fn routing_key(&self) -> Self::Key;
+}
+
+/// Routes messages to workers by their [`RoutingKey`].
+///
+/// Workers are placed on a consistent-hash ring,
+/// so adding or removing a worker only moves the keys of that worker's neighbours on the ring.
+pub struct HashRouter<A: Actor> {
+    workers: HashMap<ContextID, Addr<A>>,
+    ring: BTreeMap<u64, ContextID>,
+}
+
+impl<A: Actor> Default for HashRouter<A> {
+    fn default() -> Self {
+        Self {
+            workers: HashMap::new(),
+            ring: BTreeMap::new(),
+        }
+    }
+}
+
+impl<A: Actor> FromIterator<Addr<A>> for HashRouter<A> {
+    fn from_iter<I: IntoIterator<Item = Addr<A>>>(workers: I) -> Self {
+        let mut router = Self::default();
+        workers.into_iter().for_each(|worker| router.add(worker));
+        router
+    }
+}
+
+impl<A: Actor> HashRouter<A> {
+    /// How often every worker is placed on the ring, more points spread the keys more evenly.
+    pub const VIRTUAL_NODES: u32 = 64;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.workers.is_empty()
+    }
+
+    /// Add a worker, it takes over a share of the keys from the other workers.
+    pub fn add(&mut self, worker: Addr<A>) {
+        let id = worker.id();
+        for point in Self::points(id) {
+            self.ring.insert(point, id);
+        }
+        self.workers.insert(id, worker);
+    }
+
+    /// Remove a worker, its keys are spread across the remaining workers.
+    ///
+    /// The worker is not stopped.
+    pub fn remove(&mut self, id: ContextID) -> Option<Addr<A>> {
+        let worker = self.workers.remove(&id)?;
+        for point in Self::points(id) {
+            if self.ring.get(&point) == Some(&id) {
+                self.ring.remove(&point);
+            }
+        }
+        Some(worker)
+    }
+
+    /// The worker that handles messages with `key`.
+    pub fn worker_for<K: Hash>(&self, key: &K) -> Option<&Addr<A>> {
+        let point = hash(key);
+        let (_, id) = self
+            .ring
+            .range(point..)
+            .next()
+            .or_else(|| self.ring.iter().next())?;
+        self.workers.get(id)
+    }
+
+    /// Send a message to the worker for its key.
+    pub async fn send<M: RoutingKey<Response = ()>>(&self, msg: M) -> Result<()>
+    where
+        A: Handler<M>,
+    {
+        self.pick(&msg)?.send(msg).await
+    }
+
+    /// Call the worker for the message's key.
+    pub async fn call<M: RoutingKey>(&self, msg: M) -> Result<M::Response>
+    where
+        A: Handler<M>,
+    {
+        self.pick(&msg)?.call(msg).await
+    }
+
+    fn pick<M: RoutingKey>(&self, msg: &M) -> Result<&Addr<A>> {
+        self.worker_for(&msg.routing_key())
+            .ok_or(ActorError::EmptyPool)
+    }
+
+    fn points(id: ContextID) -> impl Iterator<Item = u64> {
+        (0..Self::VIRTUAL_NODES).map(move |replica| hash(&(id, replica)))
+    }
+}
+
+/// Hashes the same on every run, unlike a `HashMap`'s hasher.
+fn hash<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::{HashRouter, RoutingKey};
+    use crate::{Addr, prelude::*};
+
+    #[derive(Debug, Default)]
+    struct Sessions(Vec<u32>);
+    impl Actor for Sessions {}
+
+    struct Event {
+        session: u32,
+    }
+    impl Message for Event {
+        type Response = ();
+    }
+    impl RoutingKey for Event {
+        type Key = u32;
+        fn routing_key(&self) -> u32 {
+            self.session
+        }
+    }
+
+    struct Seen;
+    impl Message for Seen {
+        type Response = Vec<u32>;
+    }
+
+    impl Handler<Event> for Sessions {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, event: Event) {
+            self.0.push(event.session);
+        }
+    }
+
+    impl Handler<Seen> for Sessions {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, _: Seen) -> Vec<u32> {
+            self.0.clone()
+        }
+    }
+
+    fn spawn_workers(n: usize) -> Vec<Addr<Sessions>> {
+        (0..n).map(|_| Sessions::default().spawn()).collect()
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn same_key_same_worker() {
+        let workers = spawn_workers(4);
+        let router = workers.iter().cloned().collect::<HashRouter<_>>();
+        for session in (0..20).chain(0..20) {
+            router.send(Event { session }).await.unwrap();
+        }
+
+        let mut all = Vec::new();
+        for worker in &workers {
+            let seen = worker.call(Seen).await.unwrap();
+            for session in &seen {
+                assert_eq!(router.worker_for(session).unwrap().id(), worker.id());
+            }
+            all.extend(seen);
+        }
+        all.sort_unstable();
+        assert_eq!(all.len(), 40);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn adding_and_removing_workers_moves_few_keys() {
+        let mut router = spawn_workers(4).into_iter().collect::<HashRouter<_>>();
+        let owners = |router: &HashRouter<Sessions>| {
+            (0..1000u32)
+                .map(|key| router.worker_for(&key).unwrap().id())
+                .collect::<Vec<_>>()
+        };
+        let before = owners(&router);
+
+        let added = Sessions::default().spawn();
+        let added_id = added.id();
+        router.add(added);
+        let after = owners(&router);
+
+        let moved = before.iter().zip(&after).filter(|(a, b)| a != b);
+        assert!(moved.clone().all(|(_, owner)| *owner == added_id));
+        assert!(moved.count() < 400, "only the new worker's share moves");
+
+        router.remove(added_id).unwrap();
+        assert_eq!(owners(&router), before);
+    }
+}
@@ -0,0 +1,534 @@
+use futures::{FutureExt as _, channel::oneshot};
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher as _,
+    sync::{
+        Arc, Mutex, PoisonError, RwLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use crate::{
+    Actor, Addr, Caller, Handler, Message, Sender, StopReason, WeakCaller, WeakSender,
+    addr::caller::CallFuture,
+    context::{ContextID, RunningFuture},
+    error::{ActorError, Result},
+};
+
+type SpawnFn<A> = Box<dyn FnMut() -> Addr<A> + Send>;
+
+/// How a [`Pool`] picks the worker for the next message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Routing {
+    /// One worker after the other.
+    #[default]
+    RoundRobin,
+    /// Any worker, picked at random.
+    Random,
+    /// The worker with the fewest messages in its mailbox, see [`Addr::mailbox_len`].
+    LeastLoaded,
+}
+
+/// A group of identical actors that share the work.
+///
+/// Every message sent to the pool is handled by exactly one worker, picked according to its [`Routing`].
+/// Workers that stopped are replaced by fresh ones.
+/// Spawn a pool via [`spawn_pool`](`crate::build`) or [`Pool::new`].
+///
+/// Pools are cheap to clone, all clones share the same workers.
+/// Like an [`Addr`], a pool hands out [`Sender`]s and [`Caller`]s that route every message to one of the workers.
+///
+/// ```no_run
+/// # use hannibal::{prelude::*, Caller, Routing};
+/// #[derive(Clone, Default)]
+/// struct Resizer;
+/// impl Actor for Resizer {}
+///
+/// #[message(response = Vec<u8>)]
+/// struct Resize(Vec<u8>);
+///
+/// impl Handler<Resize> for Resizer {
+///     async fn handle(&mut self, _: &mut Context<Self>, Resize(image): Resize) -> Vec<u8> {
+///         image
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let pool = hannibal::build(Resizer)
+///     .bounded(16)
+///     .spawn_pool(4)
+///     .with_routing(Routing::LeastLoaded);
+///
+/// let thumbnail = pool.call(Resize(vec![1, 2, 3])).await.unwrap();
+///
+/// let resize: Caller<Resize> = pool.caller();
+/// pool.resize(8);
+/// let thumbnail = resize.call(Resize(vec![4, 5, 6])).await.unwrap();
+/// # }
+/// ```
+pub struct Pool<A: Actor> {
+    inner: Arc<Inner<A>>,
+}
+
+struct Inner<A: Actor> {
+    id: ContextID,
+    workers: RwLock<Vec<Addr<A>>>,
+    routing: RwLock<Routing>,
+    next: AtomicUsize,
+    spawn: Mutex<SpawnFn<A>>,
+    stopped: Mutex<Option<oneshot::Sender<StopReason>>>,
+    running: RunningFuture,
+}
+
+impl<A: Actor> Clone for Pool<A> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<A: Actor> Pool<A> {
+    /// Create a pool of `size` workers, each spawned by `spawn`.
+    ///
+    /// `spawn` is called again whenever the pool [grows](`Pool::resize`) or a worker stopped.
+    pub fn new(size: usize, spawn: impl FnMut() -> Addr<A> + Send + 'static) -> Self {
+        let (stopped, running) = oneshot::channel();
+        let pool = Self {
+            inner: Arc::new(Inner {
+                id: ContextID::default(),
+                workers: RwLock::new(Vec::with_capacity(size)),
+                routing: RwLock::new(Routing::default()),
+                next: AtomicUsize::new(0),
+                spawn: Mutex::new(Box::new(spawn)),
+                stopped: Mutex::new(Some(stopped)),
+                running: running.shared(),
+            }),
+        };
+        pool.resize(size);
+        pool
+    }
+
+    /// Route messages differently, [`Routing::RoundRobin`] by default.
+    pub fn with_routing(self, routing: Routing) -> Self {
+        *self
+            .inner
+            .routing
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = routing;
+        self
+    }
+
+    pub fn routing(&self) -> Routing {
+        *self
+            .inner
+            .routing
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Identifies the pool, e.g. in the [`Sender`]s it hands out.
+    pub fn id(&self) -> ContextID {
+        self.inner.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.workers().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.workers().is_empty()
+    }
+
+    /// The addresses of all workers.
+    pub fn workers(&self) -> Vec<Addr<A>> {
+        self.inner.workers().clone()
+    }
+
+    /// Spawn or stop workers until there are `size` of them.
+    ///
+    /// Workers that are no longer needed handle the messages they already received before they stop.
+    pub fn resize(&self, size: usize) {
+        // the spawn fn may use the pool, so it never runs while the workers are locked
+        let missing = size.saturating_sub(self.len());
+        let mut spawned = self.spawn_workers(missing);
+        let retired = {
+            let mut workers = self
+                .inner
+                .workers
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            while workers.len() < size {
+                let Some(worker) = spawned.pop() else { break };
+                workers.push(worker);
+            }
+            let kept = size.min(workers.len());
+            let retired = workers.split_off(kept);
+            spawned.extend(retired);
+            spawned
+        };
+        retire(retired);
+        log::trace!("resized pool of {} to {size}", A::NAME);
+    }
+
+    /// Stop all workers.
+    ///
+    /// The pool stays usable, [`resize`](`Pool::resize`) it to spawn new workers.
+    /// [`Caller`]s of the pool treat it as stopped though.
+    pub fn stop(&self) {
+        self.resize(0);
+        let stopped = self
+            .inner
+            .stopped
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(stopped) = stopped {
+            let _ = stopped.send(StopReason::Stopped);
+        }
+    }
+
+    /// Send a message to one of the workers.
+    pub async fn send<M: Message<Response = ()>>(&self, msg: M) -> Result<()>
+    where
+        A: Handler<M>,
+    {
+        self.pick()?.send(msg).await
+    }
+
+    /// Call one of the workers.
+    pub async fn call<M: Message>(&self, msg: M) -> Result<M::Response>
+    where
+        A: Handler<M>,
+    {
+        self.pick()?.call(msg).await
+    }
+
+    /// A [`Sender`] that routes every message to one of the workers.
+    ///
+    /// The sender keeps the pool alive, but not its workers once the pool is dropped.
+    pub fn sender<M: Message<Response = ()>>(&self) -> Sender<M>
+    where
+        A: Handler<M>,
+    {
+        let send_pool = self.clone();
+        let force_pool = self.clone();
+        let weak = Arc::downgrade(&self.inner);
+        let id = self.id();
+        Sender::from_fns(
+            id,
+            move |msg| {
+                let pool = send_pool.clone();
+                Box::pin(async move { pool.send(msg).await })
+            },
+            move |msg| force_pool.pick()?.force_send(msg),
+            move || {
+                let weak = std::sync::Weak::clone(&weak);
+                WeakSender::from_upgrade(id, A::NAME, move || {
+                    weak.upgrade().map(|inner| Pool { inner }.sender())
+                })
+            },
+        )
+    }
+
+    /// A [`Caller`] that routes every call to one of the workers.
+    ///
+    /// The caller keeps the pool alive, but not its workers once the pool is dropped.
+    pub fn caller<M: Message>(&self) -> Caller<M>
+    where
+        A: Handler<M>,
+    {
+        let pool = self.clone();
+        let weak = Arc::downgrade(&self.inner);
+        let id = self.id();
+        Caller::from_fns(
+            id,
+            self.inner.running.clone(),
+            move |msg, cancellation| -> CallFuture<M::Response> {
+                match pool.pick() {
+                    Ok(worker) => worker
+                        .caller::<M>()
+                        .call_with_cancellation(msg, cancellation),
+                    Err(error) => Box::pin(async move { Err(error) }),
+                }
+            },
+            move || {
+                let weak = std::sync::Weak::clone(&weak);
+                WeakCaller::from_upgrade(id, move || {
+                    weak.upgrade().map(|inner| Pool { inner }.caller())
+                })
+            },
+        )
+    }
+
+    /// The worker for the next message, stopped workers are replaced first.
+    fn pick(&self) -> Result<Addr<A>> {
+        if self.inner.workers().iter().any(has_stopped) {
+            self.replace_stopped();
+        }
+        let workers = self.inner.workers();
+        let len = workers.len();
+        if len == 0 {
+            return Err(ActorError::EmptyPool);
+        }
+        let worker = match self.routing() {
+            Routing::RoundRobin => {
+                workers.get(self.inner.next.fetch_add(1, Ordering::Relaxed) % len)
+            }
+            Routing::Random => {
+                // every `RandomState` is seeded differently, good enough to spread the load
+                let random =
+                    RandomState::new().hash_one(self.inner.next.fetch_add(1, Ordering::Relaxed));
+                usize::try_from(random % len as u64)
+                    .ok()
+                    .and_then(|index| workers.get(index))
+            }
+            Routing::LeastLoaded => workers.iter().min_by_key(|worker| worker.mailbox_len()),
+        };
+        worker.cloned().ok_or(ActorError::EmptyPool)
+    }
+
+    fn replace_stopped(&self) {
+        let stopped = self
+            .inner
+            .workers()
+            .iter()
+            .filter(|worker| has_stopped(worker))
+            .count();
+        let mut spawned = self.spawn_workers(stopped);
+        {
+            let mut workers = self
+                .inner
+                .workers
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            for worker in workers.iter_mut().filter(|worker| has_stopped(worker)) {
+                let Some(replacement) = spawned.pop() else {
+                    break;
+                };
+                log::debug!("replacing stopped worker {} of {}", worker.id(), A::NAME);
+                *worker = replacement;
+            }
+        }
+        // another caller replaced the same workers in the meantime
+        retire(spawned);
+    }
+
+    fn spawn_workers(&self, count: usize) -> Vec<Addr<A>> {
+        let mut spawn = self.inner.spawn();
+        (0..count).map(|_| spawn()).collect()
+    }
+}
+
+fn retire<A: Actor>(workers: Vec<Addr<A>>) {
+    for mut worker in workers {
+        if let Err(error) = worker.stop() {
+            log::debug!("{} was already stopped: {error}", A::NAME);
+        }
+    }
+}
+
+impl<A: Actor> Inner<A> {
+    fn workers(&self) -> std::sync::RwLockReadGuard<'_, Vec<Addr<A>>> {
+        self.workers.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn spawn(&self) -> std::sync::MutexGuard<'_, SpawnFn<A>> {
+        self.spawn.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn has_stopped<A: Actor>(worker: &Addr<A>) -> bool {
+    worker.running.clone().now_or_never().is_some()
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use futures::future::join_all;
+
+    use std::sync::{Arc, Mutex, OnceLock};
+
+    use super::{Pool, Routing};
+    use crate::{Addr, prelude::*};
+
+    #[derive(Clone, Debug, Default)]
+    struct Worker(usize);
+    impl Actor for Worker {}
+
+    struct Job;
+    impl Message for Job {
+        type Response = ();
+    }
+
+    struct Handled;
+    impl Message for Handled {
+        type Response = usize;
+    }
+
+    impl Handler<Job> for Worker {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, _: Job) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            self.0 += 1;
+        }
+    }
+
+    struct Pause(Duration);
+    impl Message for Pause {
+        type Response = ();
+    }
+
+    impl Handler<Pause> for Worker {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, Pause(duration): Pause) {
+            tokio::time::sleep(duration).await;
+        }
+    }
+
+    impl Handler<Handled> for Worker {
+        async fn handle(&mut self, _ctx: &mut Context<Self>, _: Handled) -> usize {
+            self.0
+        }
+    }
+
+    async fn handled(workers: &[Addr<Worker>]) -> Vec<usize> {
+        join_all(workers.iter().map(|worker| worker.call(Handled)))
+            .await
+            .into_iter()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn round_robin_spreads_evenly() {
+        let pool = crate::build(Worker::default()).unbounded().spawn_pool(3);
+        for _ in 0..6 {
+            pool.send(Job).await.unwrap();
+        }
+        assert_eq!(handled(&pool.workers()).await, vec![2, 2, 2]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn least_loaded_picks_shortest_mailbox() {
+        let pool = crate::build(Worker::default())
+            .unbounded()
+            .spawn_pool(2)
+            .with_routing(Routing::LeastLoaded);
+        let workers = pool.workers();
+        let busy = workers.first().unwrap();
+        // keeps the three jobs in the busy worker's mailbox while the pool routes
+        busy.send(Pause(Duration::from_millis(200))).await.unwrap();
+        for _ in 0..3 {
+            busy.send(Job).await.unwrap();
+        }
+        for _ in 0..3 {
+            pool.send(Job).await.unwrap();
+        }
+        assert_eq!(handled(&pool.workers()).await, vec![3, 3]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn random_routing_reaches_workers() {
+        let pool = crate::build(Worker::default())
+            .unbounded()
+            .spawn_pool(2)
+            .with_routing(Routing::Random);
+        for _ in 0..10 {
+            pool.send(Job).await.unwrap();
+        }
+        assert_eq!(handled(&pool.workers()).await.iter().sum::<usize>(), 10);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn resizes_at_runtime() {
+        let pool = crate::build(Worker::default()).unbounded().spawn_pool(2);
+        pool.resize(4);
+        assert_eq!(pool.len(), 4);
+        for _ in 0..4 {
+            pool.send(Job).await.unwrap();
+        }
+        assert_eq!(handled(&pool.workers()).await, vec![1, 1, 1, 1]);
+
+        let retired = pool.workers().iter().skip(1).cloned().collect::<Vec<_>>();
+        pool.resize(1);
+        for worker in retired {
+            assert_eq!(worker.await, StopReason::Stopped);
+        }
+
+        pool.stop();
+        assert_eq!(
+            pool.call(Handled).await,
+            Err(crate::error::ActorError::EmptyPool)
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn hands_out_sender_and_caller() {
+        let pool = crate::build(Worker::default()).unbounded().spawn_pool(2);
+        let sender: crate::Sender<Job> = pool.sender();
+        let caller: crate::Caller<Handled> = pool.caller();
+
+        sender.send(Job).await.unwrap();
+        sender.send(Job).await.unwrap();
+        assert_eq!(handled(&pool.workers()).await, vec![1, 1]);
+        assert_eq!(caller.call(Handled).await, Ok(1));
+
+        // the sender routes to workers added later on, too
+        pool.resize(3);
+        for _ in 0..3 {
+            sender.send(Job).await.unwrap();
+        }
+        assert_eq!(handled(&pool.workers()).await, vec![2, 2, 1]);
+
+        let weak_caller = caller.downgrade();
+        drop((pool, sender, caller));
+        assert!(weak_caller.upgrade().is_none());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn replaces_stopped_workers() {
+        let pool = crate::build(Worker::default()).unbounded().spawn_pool(2);
+        let mut stopped = pool.workers().first().cloned().unwrap();
+        stopped.stop().unwrap();
+        assert_eq!(stopped.clone().await, StopReason::Stopped);
+
+        for _ in 0..4 {
+            pool.send(Job).await.unwrap();
+        }
+        assert_eq!(pool.len(), 2);
+        assert!(
+            pool.workers()
+                .iter()
+                .all(|worker| worker.id() != stopped.id())
+        );
+        assert_eq!(handled(&pool.workers()).await, vec![2, 2]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn spawn_fn_can_use_the_pool() {
+        let pool = Arc::new(OnceLock::<Pool<Worker>>::new());
+        let sizes = Arc::new(Mutex::new(Vec::new()));
+        let spawned = Pool::new(1, {
+            let (pool, sizes) = (Arc::clone(&pool), Arc::clone(&sizes));
+            move || {
+                if let Some(pool) = pool.get() {
+                    sizes.lock().unwrap().push(pool.workers().len());
+                }
+                crate::build(Worker::default()).unbounded().spawn()
+            }
+        });
+        let pool = pool.get_or_init(|| spawned);
+
+        pool.resize(3);
+        pool.workers().first().cloned().unwrap().stop().unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        pool.send(Job).await.unwrap();
+
+        assert_eq!(pool.len(), 3);
+        assert_eq!(*sizes.lock().unwrap(), vec![1, 1, 3]);
+    }
+}